@@ -0,0 +1,190 @@
+// dirmod
+// Copyright (C) SOFe
+//
+// Licensed under the Apache License, Version 2.0 (the License);
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an AS IS BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # dirmod-build
+//! Generates the `mod` statements of [`dirmod`][dirmod] from a build script.
+//!
+//! The generated code is identical to the expansion of the corresponding `dirmod` macro,
+//! except that every module is declared with an absolute `#[path]`.
+//! Since the declarations are written to `OUT_DIR` as a normal file,
+//! this works on any Rust toolchain, Cargo reruns the build script
+//! when files are added to or removed from the scanned directory,
+//! and tools can read the expansion directly.
+//!
+//! In build.rs:
+//!
+//! ```ignore
+//! fn main() {
+//!     dirmod_build::Build::all("src/handlers", "default pub use")
+//!         .write("handlers.rs")
+//!         .unwrap();
+//! }
+//! ```
+//!
+//! In src/handlers.rs (or src/handlers/mod.rs, which is never declared as a module itself):
+//!
+//! ```ignore
+//! include!(concat!(env!("OUT_DIR"), "/handlers.rs"));
+//! ```
+//!
+//! The arguments have the same syntax as the arguments of the corresponding `dirmod` macro.
+//! See [`syntax.bnf`][bnf-blob] for a syntax reference.
+//!
+//! [dirmod]: https://docs.rs/dirmod
+//! [bnf-blob]: https://github.com/SOF3/dirmod/blob/master/syntax.bnf
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use proc_macro2::TokenStream;
+
+/// A directory to generate module declarations for
+#[derive(Clone, Debug)]
+pub struct Build {
+    kind: Kind,
+    dir: PathBuf,
+    args: String,
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Kind {
+    All,
+    Os,
+    Family,
+    Feature,
+    CfgKey,
+    CfgFlag,
+}
+
+impl Build {
+    /// Declares all modules in `dir`, equivalent to `dirmod::all!`.
+    ///
+    /// `dir` is relative to the crate root, and `args` is the argument list of `dirmod::all!`.
+    pub fn all(dir: impl Into<PathBuf>, args: &str) -> Self {
+        Self::new(Kind::All, dir.into(), args)
+    }
+
+    /// Declares the modules in `dir` by `target_os`, equivalent to `dirmod::os!`.
+    pub fn os(dir: impl Into<PathBuf>, args: &str) -> Self {
+        Self::new(Kind::Os, dir.into(), args)
+    }
+
+    /// Declares the modules in `dir` by `target_family`, equivalent to `dirmod::family!`.
+    pub fn family(dir: impl Into<PathBuf>, args: &str) -> Self {
+        Self::new(Kind::Family, dir.into(), args)
+    }
+
+    /// Declares the modules in `dir` by `feature`, equivalent to `dirmod::feature!`.
+    pub fn feature(dir: impl Into<PathBuf>, args: &str) -> Self {
+        Self::new(Kind::Feature, dir.into(), args)
+    }
+
+    /// Declares the modules in `dir` by an arbitrary cfg key, equivalent to `dirmod::cfg_key!`.
+    ///
+    /// `args` starts with the cfg key, e.g. `"target_arch; pub use ||"`.
+    pub fn cfg_key(dir: impl Into<PathBuf>, args: &str) -> Self {
+        Self::new(Kind::CfgKey, dir.into(), args)
+    }
+
+    /// Declares the modules in `dir` by boolean cfg flags, equivalent to `dirmod::cfg_flag!`.
+    pub fn cfg_flag(dir: impl Into<PathBuf>, args: &str) -> Self {
+        Self::new(Kind::CfgFlag, dir.into(), args)
+    }
+
+    fn new(kind: Kind, dir: PathBuf, args: &str) -> Self {
+        Self {
+            kind,
+            dir,
+            args: args.to_string(),
+        }
+    }
+
+    /// Writes the generated code to `out` relative to `OUT_DIR`,
+    /// and requests Cargo to rerun the build script when the scanned directories change.
+    ///
+    /// Returns the absolute path of the written file.
+    pub fn write(&self, out: impl AsRef<Path>) -> Result<PathBuf, Error> {
+        let out_dir = env_path("OUT_DIR")?;
+        let manifest_dir = env_path("CARGO_MANIFEST_DIR")?;
+
+        let mut ctx = dirmod_core::Context {
+            dir: Some(manifest_dir.join(&self.dir)),
+            base: None,
+            exclude: None,
+            explicit_paths: true,
+            deps: vec![],
+        };
+        let ts = self.args.parse::<TokenStream>().map_err(Error::Lex)?;
+        let ts = match self.kind {
+            Kind::All => dirmod_core::all(ts, &mut ctx),
+            Kind::Os => dirmod_core::os(ts, &mut ctx),
+            Kind::Family => dirmod_core::family(ts, &mut ctx),
+            Kind::Feature => dirmod_core::feature(ts, &mut ctx),
+            Kind::CfgKey => dirmod_core::cfg_key(ts, &mut ctx),
+            Kind::CfgFlag => dirmod_core::cfg_flag(ts, &mut ctx),
+        }
+        .map_err(Error::Dirmod)?;
+
+        let out = out_dir.join(out);
+        if let Some(parent) = out.parent() {
+            fs::create_dir_all(parent).map_err(Error::Io)?;
+        }
+        fs::write(&out, format!("// @generated by dirmod-build\n{}\n", ts)).map_err(Error::Io)?;
+
+        for dep in &ctx.deps {
+            println!("cargo:rerun-if-changed={}", dep.display());
+        }
+
+        Ok(out)
+    }
+}
+
+fn env_path(name: &'static str) -> Result<PathBuf, Error> {
+    std::env::var_os(name)
+        .map(PathBuf::from)
+        .ok_or(Error::Env(name))
+}
+
+/// An error during code generation
+#[derive(Debug)]
+pub enum Error {
+    /// An environment variable set by Cargo for build scripts is missing
+    Env(&'static str),
+    /// The arguments are not valid Rust tokens
+    Lex(proc_macro2::LexError),
+    /// The arguments are invalid or the directory cannot be scanned
+    Dirmod(syn::Error),
+    /// The generated code cannot be written
+    Io(io::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Env(name) => write!(
+                f,
+                "Environment variable {} is not set, dirmod-build must be called from build.rs",
+                name
+            ),
+            Self::Lex(err) => write!(f, "Error tokenizing arguments: {:?}", err),
+            Self::Dirmod(err) => write!(f, "{}", err),
+            Self::Io(err) => write!(f, "Error writing generated code: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
@@ -0,0 +1,66 @@
+// dirmod
+// Copyright (C) SOFe
+//
+// Licensed under the Apache License, Version 2.0 (the License);
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an AS IS BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+fn main() {
+    println!("cargo:rustc-check-cfg=cfg(dirmod_local_file)");
+    println!("cargo:rustc-check-cfg=cfg(dirmod_nightly_span)");
+    println!("cargo:rustc-check-cfg=cfg(dirmod_tracked_path)");
+
+    // `Span::local_file` was stabilized in Rust 1.88.
+    // Older nightly toolchains can still use the unstable `Span::source_file`.
+    if version_check::is_min_version("1.88.0").unwrap_or(false) {
+        println!("cargo:rustc-cfg=dirmod_local_file");
+    } else if version_check::is_feature_flaggable().unwrap_or(false) {
+        println!("cargo:rustc-cfg=dirmod_nightly_span");
+    }
+
+    // Directory tracking is only available on nightly toolchains.
+    // The API has been renamed a few times, so probe for the current one.
+    if version_check::is_feature_flaggable().unwrap_or(false)
+        && probe(
+            "#![feature(proc_macro_tracked_path)]
+            extern crate proc_macro;
+            pub fn track(path: &std::path::Path) { proc_macro::tracked::path(path) }",
+        )
+    {
+        println!("cargo:rustc-cfg=dirmod_tracked_path");
+    }
+}
+
+/// Checks whether `code` compiles as a library with the current compiler
+fn probe(code: &str) -> bool {
+    let out_dir = match env::var_os("OUT_DIR") {
+        Some(dir) => PathBuf::from(dir),
+        None => return false,
+    };
+    let src = out_dir.join("probe.rs");
+    if fs::write(&src, code).is_err() {
+        return false;
+    }
+
+    let rustc = env::var_os("RUSTC").unwrap_or_else(|| "rustc".into());
+    Command::new(rustc)
+        .args(["--edition=2018", "--crate-type=lib", "--emit=metadata"])
+        .arg("--out-dir")
+        .arg(&out_dir)
+        .arg(&src)
+        .status()
+        .is_ok_and(|status| status.success())
+}
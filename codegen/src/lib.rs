@@ -13,20 +13,26 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-#![feature(proc_macro_span)]
+#![cfg_attr(dirmod_nightly_span, feature(proc_macro_span))]
+#![cfg_attr(dirmod_tracked_path, feature(proc_macro_tracked_path))]
 
-extern crate proc_macro;
+#[cfg(not(any(dirmod_local_file, dirmod_nightly_span)))]
+compile_error!("dirmod requires Rust 1.88 or above, or a nightly toolchain");
 
-fn context(ctx: &'static str) -> impl Fn(syn::Error) -> syn::Error {
-    move |err| syn::Error::new(err.span(), format!("Error during {}: {}", ctx, err))
-}
+extern crate proc_macro;
 
 macro_rules! decl {
     ($name:ident: $(#[$docs:meta])*) => {
         #[proc_macro]
         $(#[$docs])*
         pub fn $name(ts: proc_macro::TokenStream) -> proc_macro::TokenStream {
-            let ret = match imp::$name(ts.into()) {
+            let ret = match source::context().and_then(|mut ctx| {
+                let ts = dirmod_core::$name(ts.into(), &mut ctx)?;
+                for dep in &ctx.deps {
+                    source::track(dep);
+                }
+                Ok(ts)
+            }) {
                 Ok(ts) => ts,
                 Err(err) => err.to_compile_error(),
             }.into();
@@ -48,14 +54,87 @@ decl!(all:
       ///   would be appended.
       ///   If this statement is not given, `priv` is assumed for default.
       ///   The extra `file`/`dir` keyword restricts the scope of this default setting to all .rs
-      ///   files or all directories with mod.rs respectively.
+      ///   files or all directories respectively. A directory module is either a directory with
+      ///   mod.rs, or a `foo.rs` file with a sibling `foo/` directory.
       ///   Note that it does not make sense to use all three of `default`, `default file` and
       ///   `default dir` together. If `default` is used together with one of `default file` or
       ///   `default dir`, it will only cover directory/file modules respectively.
+      ///   Outer attributes like `#[allow(dead_code)]` can be written before `$vis`
+      ///   to add them to the `mod` items, or before `use` to add them to the `use` items,
+      ///   e.g. `default #[allow(dead_code)] pub #[doc(inline)] use`.
       /// - `$vis [use] $name1, $name2, ...`: The specified modules have `$vis`
       ///   visibility, different from the default visibility.
-      ///   The format of `$vis [use]` is identical to that in `default`.
+      ///   The format of `$vis [use]`, including attributes, is identical to that in `default`.
       /// - `except $name1 $name2 ...`: The specified modules are excluded.
+      /// - `only $name1, $name2, ...`: Only the specified modules are declared.
+      ///   In recursive mode, listing an inline module selects all modules inside it.
+      /// - `cfg($predicate) $name1, $name2, ...`: The specified modules, and their `use`
+      ///   re-exports, are declared with `#[cfg($predicate)]`. A module matched by multiple
+      ///   `cfg` statements is only compiled if all of their predicates are satisfied.
+      /// - `tests $name1, $name2, ...`: The specified modules are test-only. Test-only modules
+      ///   are declared with `#[cfg(test)]`, and their items are never re-exported by `use`.
+      ///   If this statement is not given, modules named `tests`, `*_tests`, `test_util` and
+      ///   `testing` are test-only. `tests` without any names disables test-only modules.
+      /// - `prefer file $name1, $name2, ...` or `prefer dir $name1, $name2, ...`:
+      ///   If both `foo.rs` and `foo/mod.rs` exist, the module is reported as an error
+      ///   unless one of them is chosen with this statement.
+      ///   The chosen file is declared with a `#[path]` attribute,
+      ///   so submodules of a chosen `foo.rs` are located next to it instead of in `foo/`.
+      /// - `in "$path"` or `in crate "$path"`: Scans `$path` instead of the directory of the
+      ///   calling module. The path is relative to the directory of the calling file,
+      ///   or to the crate root (`CARGO_MANIFEST_DIR`) if `crate` is given,
+      ///   and must not point outside the workspace.
+      ///   Modules are declared with absolute `#[path]` attributes.
+      ///   `in crate` also works where the calling file cannot be located,
+      ///   e.g. when dirmod is invoked from another macro.
+      ///   `in out_dir "$path"` scans a directory in `OUT_DIR` like [`out_dir!`](macro.out_dir.html).
+      /// - `lints`: Modules in `OUT_DIR` are declared with `#[allow(clippy::all)]` by default.
+      ///   This statement removes the attribute.
+      /// - `recursive`: Subdirectories without mod.rs or a sibling .rs file are declared as
+      ///   inline modules (`mod foo { ... }`) containing the modules in the subdirectory,
+      ///   recursively. The `default` settings apply to all levels, and nested modules can be
+      ///   named by their paths relative to the calling module, e.g. `except foo::bar`.
+      ///   Excluding an inline module excludes all modules inside it.
+      /// - `rename error` or `rename sanitize [$prefix]`: Specifies how to handle file names
+      ///   that are not valid identifiers. Keywords like `type.rs` are always declared as raw
+      ///   identifiers (`r#type`). Other invalid names like `my-module.rs` or `001_init.rs` are
+      ///   reported as errors by default. With `rename sanitize`, invalid characters are replaced
+      ///   with `_`, names starting with a digit are prefixed with `$prefix` (`"_"` by default),
+      ///   and the module is declared with a `#[path]` attribute pointing to the original file.
+      ///   Refer to sanitized modules by their sanitized names in other statements.
+      /// - `order alphabetical`, `order natural` or `order $name1, $name2, ...`: Specifies the
+      ///   order of the generated declarations, which affects `macro_rules!` visibility and the
+      ///   order of glob re-exports. Modules are sorted by name (`alphabetical`) by default.
+      ///   `natural` compares digit sequences numerically, so `2_x` comes before `10_x`.
+      ///   With an explicit list, the listed modules come first in the given order,
+      ///   followed by the other modules sorted by name.
+      ///   A single module named `natural` or `alphabetical` can be listed as `order natural,`.
+      ///
+      /// Module names in these lists can also be patterns matched against the module path:
+      /// `"*_test"` is a glob pattern, where `*` and `?` do not match `::` but `**` does,
+      /// and `regex "^v[0-9]+$"` is a regex that must match the whole path.
+      /// Exact names take precedence over patterns,
+      /// but it is an error if a module matches multiple visibility patterns,
+      /// or both a visibility pattern and an `except` pattern.
+      /// Exact names that do not refer to any module are reported as errors,
+      /// while patterns are allowed to match nothing.
+      ///
+      /// If `all!` is called from mod.rs, lib.rs, main.rs or another crate root like
+      /// `src/bin/tool.rs` or `examples/tool.rs`, the directory containing the file is scanned.
+      /// If it is called from any other file `foo.rs`, the `foo/` directory next to it is
+      /// scanned instead, following the Rust 2018 module layout.
+      ///
+      /// Files named `$name.$target.rs` are target-specific variants of `$name`,
+      /// where `$target` is a `target_family` (`unix`, `wasm` or `windows`)
+      /// or one of the `target_os` values `aix`, `android`, `cygwin`, `dragonfly`, `emscripten`,
+      /// `freebsd`, `fuchsia`, `haiku`, `hurd`, `illumos`, `ios`, `linux`, `macos`, `netbsd`,
+      /// `openbsd`, `redox`, `solaris`, `tvos`, `visionos`, `wasi` and `watchos`.
+      /// Each variant is declared as `#[cfg(...)] #[path = "$name.$target.rs"] mod $name;`,
+      /// and `$name.rs`, if it exists, is only compiled if none of the variants match.
+      /// OS variants take precedence over family variants, so `foo.linux.rs` is compiled
+      /// instead of `foo.unix.rs` on Linux, and `wasm` takes precedence over `unix`
+      /// for targets like emscripten that belong to both families.
+      /// The statements above refer to all variants by `$name`.
       ///
       /// For simplicity, there is no special syntax to add doc comments.
       /// To document modules, either use the `//!` inner documentation
@@ -80,6 +159,52 @@ decl!(all:
       ///     except ipsum;
       /// }
       /// ```
+      ///
+      /// ```ignore
+      /// all!(recursive; default pub; except foo::bar::qux);
+      /// ```
+      ///
+      /// ```ignore
+      /// all!(order macros, prelude);
+      /// ```
+      ///
+      /// ```ignore
+      /// all!(default pub; pub(crate) "internal_*"; except "*_test", scratch);
+      /// ```
+      ///
+      /// ```ignore
+      /// all!(default pub use; cfg(feature = "serde") serde_impl, json);
+      /// ```
+      ///
+      /// ```ignore
+      /// all!(default pub; #[doc(hidden)] pub internal; #[rustfmt::skip] priv generated);
+      /// ```
+      );
+
+decl!(out_dir:
+      /// Include all modules generated by the build script in a directory of `OUT_DIR`
+      ///
+      /// # Parameters
+      /// ```ignore
+      /// out_dir!($path [, $vis [use]] [; $statements]);
+      /// ```
+      ///
+      /// All .rs files in the `$path` subdirectory of `OUT_DIR` are declared
+      /// with `#[path]` attributes, with `$vis [use]` as the default visibility.
+      /// Further statements from [`all!`](macro.all.html) can be added after a semicolon.
+      /// This is equivalent to `all!(in out_dir "$path"; default $vis [use]; $statements)`.
+      ///
+      /// Generated modules are declared with `#[allow(clippy::all)]`,
+      /// unless the `lints` statement is given.
+      ///
+      /// # Examples
+      /// ```ignore
+      /// out_dir!("protos", pub use);
+      /// ```
+      ///
+      /// ```ignore
+      /// out_dir!("bindings", pub; lints; except internal);
+      /// ```
       );
 
 decl!(os:
@@ -89,11 +214,11 @@ decl!(os:
       /// `#[cfg(target_os = $mod)]` option, allowing OS-specific module
       /// files/directories like `windows.rs`, `unix.rs`, etc.
       ///
-      /// Note that this macro does not check for nonexistent `target_os`
-      /// values, so incorrect usage will not lead to any warnings
-      /// (and likely never compile the incorrect modules).
+      /// Module names are checked against the known `target_os` values,
+      /// so typos like `linix.rs` or `macOS.rs` are reported as errors.
       /// See [this page](https://doc.rust-lang.org/reference/conditional-compilation.html)
       /// for a list of possible values.
+      /// Modules for custom targets can be allowed with `custom $name1, $name2, ...`.
       ///
       /// It is usually a good idea to provide the `use` keyword and expose
       /// the same API methods in all specific operating systems, preventing
@@ -101,11 +226,13 @@ decl!(os:
       ///
       /// # Parameters
       /// ```ignore
-      /// os!($vis [use] [|| [$error]]);
+      /// os!($vis [use] [|| [$error | $fallback]]);
       /// ```
       ///
-      /// `os!` accepts a visibility and an optional `use` keyword, with the
-      /// same meaning as those in [`all!`](macro.all.html).
+      /// `os!` accepts a visibility and an optional `use` keyword, optionally with attributes,
+      /// with the same meaning as those in [`all!`](macro.all.html).
+      /// The `rename`, `in` and `order` statements from `all!`,
+      /// as well as the `custom` statement, can also be added after a semicolon.
       ///
       /// The optional `|| $error` clause adds the code to check if at least
       /// one of the modules is compiled; otherwise,
@@ -115,10 +242,28 @@ decl!(os:
       /// `"target_os must be one of \"xxx\", \"yyy\", ..."`,
       /// where xxx and yyy are the available modules.
       ///
-      /// `os!` does not provide any filtering, and is intended for parent
-      /// modules with only platform-specific submodules.  If non-OS-specific
-      /// modules are desired, consider moving the OS-specific modules to the
-      /// same directory.
+      /// Instead of an error, `|| $fallback` compiles the module `$fallback`
+      /// under `#[cfg(not(any(target_os = "xxx", target_os = "yyy", ...)))]`,
+      /// e.g. a generic POSIX or stub implementation for the other operating systems.
+      /// The fallback module is not compiled with `#[cfg(target_os = "$fallback")]`.
+      ///
+      /// Other modules in the same directory can be declared with the following statements:
+      /// - `$vis [use] $name1, $name2, ...`: The specified modules are common modules,
+      ///   which are declared unconditionally with the given visibility
+      ///   before the OS-specific modules.
+      /// - `except $name1, $name2, ...`: The specified modules are not declared.
+      /// - `default $vis [use]`: An alternative to the leading `$vis [use]` without `||`.
+      /// - `[$vis] as $alias`: The compiled module is also imported as `$alias`
+      ///   (`#[cfg(...)] $vis use self::$mod as $alias;`), and `$vis const SELECTED: &str`
+      ///   is declared with the name of that module. This is useful if the modules
+      ///   define items with conflicting names, which cannot be imported with `use`.
+      ///   If several modules are compiled, e.g. for two enabled features,
+      ///   the first one in the `order` is selected.
+      ///   `$vis` cannot be more visible than the modules themselves.
+      ///
+      /// As in `all!`, these names can also be glob or regex patterns.
+      /// Alternatively, name platform-specific files like `foo.linux.rs`
+      /// and declare them with [`all!`](macro.all.html).
       ///
       /// # Examples
       /// ```ignore
@@ -131,6 +276,22 @@ decl!(os:
       ///
       /// If none of the modules in the directory get compiled, compilation
       /// would abort with the message "Unsupported operating system".
+      ///
+      /// ```ignore
+      /// os!(pub use || generic);
+      /// ```
+      ///
+      /// ```ignore
+      /// os!(pub use ||; pub common; except scratch);
+      /// ```
+      ///
+      /// ```ignore
+      /// os!(||; as imp);
+      /// ```
+      ///
+      /// `imp::open()` then calls `open()` in the module for the current OS.
+      ///
+      /// `generic.rs` is compiled if none of the other modules match the target.
       );
 
 decl!(family:
@@ -138,10 +299,10 @@ decl!(family:
       ///
       /// This macro is identical to [`os!`](macro.os.html), except `target_os`
       /// is replaced with `target_family`, hence only accepting
-      /// `windows.rs` and `unix.rs`.
+      /// `unix.rs`, `wasm.rs` and `windows.rs`.
       ///
-      /// However, similar to `os!`, this macro does not validate values.
-      /// Use the `||` syntax to report errors correctly.
+      /// Similar to `os!`, module names are checked against the known `target_family` values,
+      /// and the `custom` statement allows other names.
       );
 
 decl!(feature:
@@ -153,11 +314,97 @@ decl!(feature:
       ///
       /// # Parameters
       /// ```ignore
-      /// feature!($vis [use] [|| [$error]]);
+      /// feature!($vis [use] [|| [$error | $fallback]]);
       /// ```
       ///
       /// See [`os!](macro.os.html) for explanation of the parameter values.
+      ///
+      /// Module names are checked against the features declared in the crate's Cargo.toml,
+      /// including the implicit features of optional dependencies.
+      /// Since hyphens cannot appear in module names,
+      /// the module `serde_json` is compiled with the feature `serde-json` if it is declared.
+      /// A module that matches no declared feature is a compile error.
+      ///
+      /// A module `foo` may have a disabled-feature stub, `foo.disabled.rs` or `disabled/foo.rs`,
+      /// which is declared as `foo` with the same visibility when the feature is disabled.
+      /// The stub usually exposes the same API as `foo`, returning errors,
+      /// so that callers need not check the feature themselves.
+      ///
+      /// Since Cargo features are additive, `||` is usually not useful for `feature!`.
+      /// For features that select one of several backends, the `exclusive` statement
+      /// triggers `compile_error!` if none of the features are enabled (unless `||` is given)
+      /// or if more than one of them are enabled, naming the conflicting features.
+      ///
+      /// # Examples
+      /// For a directory with `tokio.rs`, `async_std.rs` and `smol.rs`:
+      /// ```ignore
+      /// feature!(pub use; exclusive);
+      /// ```
+      );
+
+decl!(cfg_key:
+      /// Includes modules based on an arbitrary `key = "value"` cfg attribute.
+      ///
+      /// Each module named `$mod` is conditionally compiled with the
+      /// `#[cfg($key = "$mod")]` attribute, where `$key` can be any cfg key
+      /// like `target_arch`, `target_env`, `target_pointer_width`, `target_endian`,
+      /// `target_vendor`, `panic`, or a custom key passed with `--cfg key="value"`.
+      /// (It is not named `cfg!` to avoid conflicts with the built-in `cfg!` in glob imports.)
+      ///
+      /// # Parameters
+      /// ```ignore
+      /// cfg_key!($key [; $vis [use] [|| [$error | $fallback]]]);
+      /// ```
+      ///
+      /// See [`os!`](macro.os.html) for explanation of the parameter values.
+      /// Unlike `os!`, file names that are not valid identifiers are sanitized by default
+      /// (`rename sanitize`), so `64.rs` is declared as `_64` with `#[cfg($key = "64")]`.
+      ///
+      /// # Examples
+      /// ```ignore
+      /// cfg_key!(target_arch; pub use ||);
+      /// ```
+      ///
+      /// ```ignore
+      /// cfg_key!(target_pointer_width; pub use || "Unsupported pointer width");
+      /// ```
+      );
+
+decl!(cfg_flag:
+      /// Includes modules based on boolean cfg flags like `test`, `debug_assertions` or `loom`.
+      ///
+      /// Each module named `$mod` is conditionally compiled with the `#[cfg($mod)]` attribute.
+      ///
+      /// # Parameters
+      /// ```ignore
+      /// cfg_flag!($vis [use] [|| [$error | $fallback]]);
+      /// ```
+      ///
+      /// See [`os!`](macro.os.html) for explanation of the visibility and `$error`.
+      ///
+      /// With `||`, exactly one module is compiled:
+      /// if multiple flags are enabled, the module listed first takes priority,
+      /// i.e. the second module is compiled with `#[cfg(all($mod2, not(any($mod1))))]`.
+      /// Modules are listed in alphabetical order unless specified with the `order` statement.
+      /// If none of the flags are enabled, `compile_error!` is triggered,
+      /// or the module `$fallback` is compiled under `#[cfg(not(any(...)))]`.
+      ///
+      /// # Examples
+      /// For a directory with `loom.rs` and `std.rs`:
+      /// ```ignore
+      /// cfg_flag!(pub use || std);
+      /// ```
+      ///
+      /// For a directory with `debug_assertions.rs` and `release.rs`:
+      /// ```ignore
+      /// cfg_flag!(pub use || release);
+      /// ```
+      ///
+      /// For a directory with `miri.rs`, `loom.rs` and `std.rs`,
+      /// where `miri` should take priority over `loom`:
+      /// ```ignore
+      /// cfg_flag!(pub use || std; order miri, loom);
+      /// ```
       );
 
-mod imp;
-mod parse;
+mod source;
@@ -0,0 +1,70 @@
+// dirmod
+// Copyright (C) SOFe
+//
+// Licensed under the Apache License, Version 2.0 (the License);
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an AS IS BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::path::{Path, PathBuf};
+
+use syn::Result;
+
+/// Creates the discovery context for the file that invoked the macro
+pub fn context() -> Result<dirmod_core::Context> {
+    let path = match source_path() {
+        Some(path) => path,
+        // only `in crate` paths can be used
+        None => return Ok(dirmod_core::Context::unlocated()),
+    };
+    // The path is usually relative to the working directory of the compiler,
+    // but generated `#[path]` attributes must not depend on it.
+    let path = match std::env::current_dir() {
+        Ok(cwd) => cwd.join(path),
+        Err(_) => path,
+    };
+    dirmod_core::Context::for_file(&path)
+}
+
+/// Returns the path of the file that invoked the macro,
+/// or `None` if the macro was not invoked from a real file.
+#[cfg(dirmod_local_file)]
+fn source_path() -> Option<PathBuf> {
+    proc_macro::Span::call_site().local_file()
+}
+
+/// Returns the path of the file that invoked the macro,
+/// or `None` if the macro was not invoked from a real file.
+#[cfg(dirmod_nightly_span)]
+fn source_path() -> Option<PathBuf> {
+    let src = proc_macro::Span::call_site().source_file();
+    if src.is_real() {
+        Some(src.path())
+    } else {
+        None
+    }
+}
+
+#[cfg(not(any(dirmod_local_file, dirmod_nightly_span)))]
+fn source_path() -> Option<PathBuf> {
+    None
+}
+
+/// Requests the compiler to expand the macro again when `path` changes,
+/// e.g. when files are added to a scanned directory.
+#[cfg(dirmod_tracked_path)]
+pub fn track(path: &Path) {
+    proc_macro::tracked::path(path);
+}
+
+/// Dependency tracking is not available on this toolchain,
+/// so changes in directories are only noticed when the calling file is recompiled.
+#[cfg(not(dirmod_tracked_path))]
+pub fn track(_: &Path) {}
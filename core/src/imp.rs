@@ -0,0 +1,2329 @@
+// dirmod
+// Copyright (C) SOFe
+//
+// Licensed under the Apache License, Version 2.0 (the License);
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an AS IS BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::ffi::{OsStr, OsString};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use proc_macro2::{Span, TokenStream};
+use quote::{format_ident, quote};
+use smallvec::SmallVec;
+use syn::ext::IdentExt;
+use syn::spanned::Spanned;
+use syn::{Error, Result};
+
+use crate::{parse, targets};
+
+macro_rules! parse_args {
+    ($args:expr, $mod:ident;
+        single: $($svar:ident),*;
+        multi: $($mvar:ident),*;
+    ) => {{
+        let args: parse::$mod::Args = $args;
+        let single = ($({
+            #[allow(irrefutable_let_patterns)]
+            let rep = args.0.iter().filter_map(|arg| {
+                if let parse::$mod::Arg::$svar(arg) = arg {
+                    Some(arg.clone())
+                } else {
+                    None
+                }
+            }).collect::<SmallVec<[_; 1]>>();
+            if rep.len() > 1 {
+                return Err(Error::new(rep[1].span(), "The statement is repeated"));
+            }
+            rep.into_iter().next()
+        },)*);
+        let multi = ($({
+            args.0.iter().filter_map(|arg| {
+                if let parse::$mod::Arg::$mvar(arg) = arg {
+                    Some(arg.clone())
+                } else {
+                    None
+                }
+            }).collect::<Vec<_>>()
+        },)*);
+        (single, multi)
+    }};
+}
+
+fn apply_modifier(
+    modifier: &parse::Modifier,
+    ident: syn::Ident,
+    path: &ModPath,
+    meta: Option<TokenStream>,
+) -> TokenStream {
+    let meta = &meta.unwrap_or_default();
+    let vis = &modifier.vis;
+    let mod_attrs = &modifier.mod_attrs;
+    let use_attrs = &modifier.use_attrs;
+    match path {
+        ModPath::Implicit | ModPath::File(_) => {
+            let path_attr = match path {
+                ModPath::File(file) => quote!(#[path = #file]),
+                _ => quote!(),
+            };
+            if let Some(import) = modifier.imports.as_ref() {
+                quote! {
+                    #meta #(#mod_attrs)* #path_attr mod #ident;
+                    #meta #(#use_attrs)* #vis #import #ident::*;
+                }
+            } else {
+                quote!(#meta #(#mod_attrs)* #path_attr #vis mod #ident;)
+            }
+        }
+        ModPath::Inline(contents, dir) => {
+            let path_attr = match dir {
+                Some(dir) => quote!(#[path = #dir]),
+                None => quote!(),
+            };
+            if let Some(import) = modifier.imports.as_ref() {
+                quote! {
+                    #meta #(#mod_attrs)* #path_attr mod #ident { #contents }
+                    #meta #(#use_attrs)* #vis #import #ident::*;
+                }
+            } else {
+                quote!(#meta #(#mod_attrs)* #path_attr #vis mod #ident { #contents })
+            }
+        }
+        ModPath::Wrapped(dir) => {
+            let wrapper = format_ident!("__dirmod_{}", ident.unraw());
+            let decl = quote!(#meta #[path = #dir] mod #wrapper { #(#mod_attrs)* pub mod #ident; });
+            if let Some(import) = modifier.imports.as_ref() {
+                quote! {
+                    #decl
+                    #meta use self::#wrapper::#ident;
+                    #meta #(#use_attrs)* #vis #import self::#wrapper::#ident::*;
+                }
+            } else {
+                quote!(#decl #meta #vis use self::#wrapper::#ident;)
+            }
+        }
+    }
+}
+
+fn parse_ts<T: syn::parse::Parse>(ts: TokenStream) -> Result<T> {
+    syn::parse2(ts).map_err(crate::context("argument parsing"))
+}
+
+pub fn all(ts: TokenStream, ctx: &mut Context) -> Result<TokenStream> {
+    all_args(parse_ts(ts)?, ctx)
+}
+
+pub fn out_dir(ts: TokenStream, ctx: &mut Context) -> Result<TokenStream> {
+    let args: parse::out_dir::Args = parse_ts(ts)?;
+    all_args(args.0, ctx)
+}
+
+fn all_args(args: parse::all::Args, ctx: &mut Context) -> Result<TokenStream> {
+    let (single, multi) = parse_args! {
+        args, all;
+        single: Recursive, Rename, Order, In, Lints, Tests;
+        multi: DefaultVis, SpecialVis, Except, Only, Cfg, Prefer;
+    };
+    let (recursive, rename, order, arg_in, lints, tests_arg) = single;
+    let (dv, sv, excepts, onlys, cfg_args, prefers) = multi;
+
+    let mut default_file = None;
+    let mut default_dir = None;
+    for dve in dv {
+        if dve.module_type.is_file() && default_file.is_none() {
+            default_file = Some(dve.modifier.clone());
+        }
+        if dve.module_type.is_dir() && default_dir.is_none() {
+            default_dir = Some(dve.modifier.clone());
+        }
+    }
+
+    let default_file = default_file.unwrap_or_else(parse::Modifier::default_file);
+    let default_dir = default_dir.unwrap_or_else(parse::Modifier::default_dir);
+
+    // exact module paths are checked against the discovered modules later
+    let mut named = Vec::new();
+    let mut compile = |pattern: &parse::ModulePattern| {
+        check_nested(pattern.is_nested(), recursive.is_some(), pattern.span())?;
+        if let parse::ModulePattern::Path(path) = pattern {
+            named.push((path.to_string(), path.span()));
+        }
+        Pattern::new(pattern)
+    };
+
+    let vis = VisOptions::new(sv, excepts, &mut compile)?;
+
+    let only = if onlys.is_empty() {
+        None
+    } else {
+        let mut only = PathSet::default();
+        for name_pattern in onlys.into_iter().flat_map(|only| only.names.into_iter()) {
+            only.insert(compile(&name_pattern)?);
+        }
+        Some(only)
+    };
+
+    let mut cfgs = Vec::new();
+    for cfg_arg in cfg_args {
+        let mut names = PathSet::default();
+        for name_pattern in &cfg_arg.names {
+            names.insert(compile(name_pattern)?);
+        }
+        cfgs.push((names, cfg_arg.meta));
+    }
+
+    let tests = match tests_arg {
+        Some(tests_arg) => {
+            let mut tests = PathSet::default();
+            for name_pattern in &tests_arg.names {
+                tests.insert(compile(name_pattern)?);
+            }
+            tests
+        }
+        None => PathSet::default_tests(),
+    };
+
+    let order = Order::from_arg(order, recursive.is_some(), &mut named)?;
+
+    let mut prefer = HashMap::new();
+    for prefer_arg in prefers {
+        let is_dir = prefer_arg.module_type.is_dir();
+        for name_path in prefer_arg.names {
+            check_nested(name_path.is_nested(), recursive.is_some(), name_path.span())?;
+            named.push((name_path.to_string(), name_path.span()));
+            if prefer
+                .insert(name_path.to_string(), (name_path.span(), is_dir))
+                .is_some()
+            {
+                return Err(Error::new(
+                    name_path.span(),
+                    "The module is preferred multiple times",
+                ));
+            }
+        }
+    }
+
+    if let Some(arg_in) = &arg_in {
+        ctx.relocate(arg_in)?;
+    }
+    // generated code is not expected to be lint-free
+    let meta = match arg_in.map(|arg_in| arg_in.base) {
+        Some(parse::InBase::OutDir(_)) if lints.is_none() => Some(quote!(#[allow(clippy::all)])),
+        _ => None,
+    };
+    let mut modules = list_mods(ctx, recursive.is_some())?;
+    let rename = Rename::from_arg(rename);
+    let mut paths = Vec::new();
+    collect_paths(&modules, "", &rename, &mut paths)?;
+    check_names(&named, &paths)?;
+    resolve_conflicts(&mut modules, "", &rename, &mut prefer)?;
+    if let Some((_, (span, _))) = prefer.into_iter().next() {
+        return Err(Error::new(
+            span,
+            "The module is not declared by both a file and a directory",
+        ));
+    }
+
+    let options = AllOptions {
+        default_file,
+        default_dir,
+        vis,
+        only,
+        cfgs,
+        tests,
+        rename,
+        order,
+        meta,
+    };
+    declare_all(
+        &modules,
+        "",
+        options.only.is_none(),
+        &options,
+        ctx.path_base(),
+    )
+}
+
+/// Rejects nested module paths like `foo::bar` unless `recursive` is used
+fn check_nested(nested: bool, recursive: bool, span: Span) -> Result<()> {
+    if nested && !recursive {
+        Err(Error::new(
+            span,
+            "Nested module paths can only be used in `recursive` mode",
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+/// Collects the `::`-separated paths of `modules` and their inline submodules.
+///
+/// Reports an error if different files are renamed to the same module,
+/// e.g. `my-module.rs` and `my_module.rs` with `rename sanitize`.
+fn collect_paths(
+    modules: &[Module],
+    prefix: &str,
+    rename: &Rename,
+    paths: &mut Vec<String>,
+) -> Result<()> {
+    let mut seen = HashMap::<String, &Module>::new();
+    for module in modules {
+        let (ident, _) = module_ident(&module.name, rename)?;
+        let path = format!("{}{}", prefix, ident.unraw());
+        // `foo.rs` and `foo/mod.rs` or `foo.unix.rs` have the same name and are resolved later
+        match seen.insert(path.clone(), module) {
+            Some(other) if other.name != module.name => {
+                return Err(Error::new(
+                    Span::call_site(),
+                    format!(
+                        "{} and {} are both declared as the module `{}`; rename one of them",
+                        other.path.display(),
+                        module.path.display(),
+                        path
+                    ),
+                ))
+            }
+            _ => {}
+        }
+        if let Some(children) = &module.children {
+            collect_paths(children, &format!("{}::", path), rename, paths)?;
+        }
+        paths.push(path);
+    }
+    Ok(())
+}
+
+/// Reports an error for each name that is not one of `paths`
+fn check_names(names: &[(String, Span)], paths: &[String]) -> Result<()> {
+    let errors = names
+        .iter()
+        .filter(|(name, _)| !paths.contains(name))
+        .map(|(name, span)| {
+            let msg = match suggest(name, paths.iter().map(String::as_str)) {
+                Some(suggestion) => format!(
+                    "There is no module `{}`; did you mean `{}`?",
+                    name, suggestion
+                ),
+                None => format!("There is no module `{}`", name),
+            };
+            Error::new(*span, msg)
+        });
+    combine_errors(errors)
+}
+
+/// Reports all `errors` together, or returns `Ok` if there are none
+fn combine_errors(errors: impl IntoIterator<Item = Error>) -> Result<()> {
+    let mut errors = errors.into_iter();
+    match errors.next() {
+        Some(mut error) => {
+            for other in errors {
+                error.combine(other);
+            }
+            Err(error)
+        }
+        None => Ok(()),
+    }
+}
+
+/// Finds the candidate most similar to `name`, if any is similar enough
+fn suggest<'t>(name: &str, candidates: impl IntoIterator<Item = &'t str>) -> Option<&'t str> {
+    let threshold = (name.chars().count() / 3).max(1);
+    candidates
+        .into_iter()
+        .map(|candidate| (strsim::levenshtein(name, candidate), candidate))
+        .filter(|&(distance, _)| distance <= threshold)
+        .min_by_key(|&(distance, _)| distance)
+        .map(|(_, candidate)| candidate)
+}
+
+/// A compiled module pattern
+enum Pattern {
+    /// An exact module path
+    Path(String),
+    /// A glob or regex pattern, matched against the whole module path
+    Regex(regex::Regex),
+}
+
+impl Pattern {
+    fn new(pattern: &parse::ModulePattern) -> Result<Self> {
+        match pattern {
+            parse::ModulePattern::Path(path) => Ok(Self::Path(path.to_string())),
+            parse::ModulePattern::Glob(glob) => Ok(Self::Regex(
+                regex::Regex::new(&glob_to_regex(&glob.value()))
+                    .expect("globs are converted to valid regex"),
+            )),
+            parse::ModulePattern::Regex(_, lit) => {
+                let regex = lit.value();
+                let invalid = |err| Error::new(lit.span(), format!("Invalid regex: {}", err));
+                // validate separately so that the error does not show the anchors
+                regex::Regex::new(&regex).map_err(invalid)?;
+                regex::Regex::new(&format!("^(?:{})$", regex))
+                    .map(Self::Regex)
+                    .map_err(invalid)
+            }
+        }
+    }
+}
+
+/// Converts a glob pattern to an anchored regex.
+///
+/// `*` and `?` match any characters within a path segment, while `**` also matches `::`.
+fn glob_to_regex(glob: &str) -> String {
+    let mut ret = String::from("^");
+    let mut chars = glob.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                ret.push_str(".*");
+            }
+            '*' => ret.push_str("[^:]*"),
+            '?' => ret.push_str("[^:]"),
+            c => ret.push_str(&regex::escape(c.encode_utf8(&mut [0; 4]))),
+        }
+    }
+    ret.push('$');
+    ret
+}
+
+/// A set of exact module paths and patterns
+#[derive(Default)]
+struct PathSet {
+    paths: HashSet<String>,
+    patterns: Vec<regex::Regex>,
+}
+
+impl PathSet {
+    /// The test-only modules if there is no `tests` statement:
+    /// `tests`, `*_tests`, `test_util` and `testing` at any level
+    fn default_tests() -> Self {
+        let regex = regex::Regex::new("^(?:.*::)?(?:tests|[^:]*_tests|test_util|testing)$")
+            .expect("the regex is valid");
+        Self {
+            paths: HashSet::new(),
+            patterns: vec![regex],
+        }
+    }
+
+    fn insert(&mut self, pattern: Pattern) {
+        match pattern {
+            Pattern::Path(path) => {
+                self.paths.insert(path);
+            }
+            Pattern::Regex(regex) => self.patterns.push(regex),
+        }
+    }
+
+    fn contains_path(&self, path: &str) -> bool {
+        self.paths.contains(path)
+    }
+
+    fn matches_pattern(&self, path: &str) -> bool {
+        self.patterns.iter().any(|regex| regex.is_match(path))
+    }
+
+    fn matches(&self, path: &str) -> bool {
+        self.contains_path(path) || self.matches_pattern(path)
+    }
+}
+
+/// The parsed arguments of `all!`
+struct AllOptions {
+    default_file: parse::Modifier,
+    default_dir: parse::Modifier,
+    vis: VisOptions,
+    /// If not `None`, only the matching modules and their submodules are declared
+    only: Option<PathSet>,
+    /// The `cfg` predicates and the modules they apply to
+    cfgs: Vec<(PathSet, syn::NestedMeta)>,
+    /// Modules only compiled in tests
+    tests: PathSet,
+    rename: Rename,
+    order: Order,
+    /// Attributes added to every module
+    meta: Option<TokenStream>,
+}
+
+/// The `except` and visibility statements
+struct VisOptions {
+    /// Modules with special visibility, keyed by their `::`-separated paths
+    special: HashMap<String, (Span, Rc<parse::Modifier>)>,
+    /// Patterns for modules with special visibility, overridden by `special`
+    special_patterns: Vec<(Span, regex::Regex, Rc<parse::Modifier>)>,
+    /// Excluded modules
+    except: PathSet,
+}
+
+impl VisOptions {
+    /// Compiles the module patterns of the statements with `compile`.
+    fn new(
+        sv: Vec<parse::ArgSpecialVis>,
+        excepts: Vec<parse::ArgExcept>,
+        compile: &mut impl FnMut(&parse::ModulePattern) -> Result<Pattern>,
+    ) -> Result<Self> {
+        let mut special = HashMap::<String, (Span, Rc<parse::Modifier>)>::new();
+        let mut special_patterns = Vec::new();
+        for sve in sv {
+            let modifier = Rc::new(sve.modifier.clone());
+            let span = sve.modifier.span();
+            for name_pattern in sve.names {
+                match compile(&name_pattern)? {
+                    Pattern::Path(name) => {
+                        if special.contains_key(&name) {
+                            return Err(Error::new(
+                                name_pattern.span(),
+                                "The module has multiple visibilities",
+                            ));
+                        }
+                        special.insert(name, (span, modifier.clone()));
+                    }
+                    Pattern::Regex(regex) => {
+                        special_patterns.push((name_pattern.span(), regex, modifier.clone()))
+                    }
+                }
+            }
+        }
+
+        let mut except = PathSet::default();
+        for name_pattern in excepts
+            .into_iter()
+            .flat_map(|except| except.names.into_iter())
+        {
+            except.insert(compile(&name_pattern)?);
+        }
+
+        Ok(Self {
+            special,
+            special_patterns,
+            except,
+        })
+    }
+
+    /// Resolves the `except` and visibility statements for the module at `path`.
+    ///
+    /// Exact paths take precedence over patterns.
+    fn resolve(&self, path: &str) -> Result<Resolution<'_>> {
+        let special = match self.special.get(path) {
+            Some((span, modifier)) => Some((*span, &**modifier, true)),
+            None => {
+                let mut matches = self
+                    .special_patterns
+                    .iter()
+                    .filter(|(_, regex, _)| regex.is_match(path));
+                let first = matches.next();
+                if let Some((span, _, _)) = matches.next() {
+                    return Err(Error::new(
+                        *span,
+                        format!("The module `{}` matches multiple visibility patterns", path),
+                    ));
+                }
+                first.map(|(span, _, modifier)| (*span, &**modifier, false))
+            }
+        };
+
+        if self.except.contains_path(path) {
+            if let Some((span, _, true)) = special {
+                return Err(Error::new(
+                    span,
+                    "The module has a special visibility but is also excluded in `except`",
+                ));
+            }
+            return Ok(Resolution::Excluded);
+        }
+        match special {
+            Some((span, _, false)) if self.except.matches_pattern(path) => Err(Error::new(
+                span,
+                format!(
+                    "The module `{}` matches a visibility pattern and an `except` pattern",
+                    path
+                ),
+            )),
+            Some((_, modifier, _)) => Ok(Resolution::Special(modifier)),
+            None if self.except.matches_pattern(path) => Ok(Resolution::Excluded),
+            None => Ok(Resolution::Default),
+        }
+    }
+}
+
+/// How a module is declared according to `except` and visibility statements
+enum Resolution<'t> {
+    Excluded,
+    Default,
+    Special(&'t parse::Modifier),
+}
+
+/// The order of generated module declarations
+enum Order {
+    /// Sort by module name
+    Alphabetical,
+    /// Sort by module name, comparing digit sequences numerically
+    Natural,
+    /// Sort by position in the explicit list keyed by module paths,
+    /// followed by unlisted modules in alphabetical order
+    Explicit(HashMap<String, usize>),
+}
+
+impl Order {
+    /// Converts an `order` statement, appending the explicitly listed paths to `named`
+    fn from_arg(
+        arg: Option<parse::ArgOrder>,
+        recursive: bool,
+        named: &mut Vec<(String, Span)>,
+    ) -> Result<Self> {
+        Ok(match arg.map(|arg| arg.rule) {
+            None | Some(parse::OrderRule::Alphabetical(_)) => Self::Alphabetical,
+            Some(parse::OrderRule::Natural(_)) => Self::Natural,
+            Some(parse::OrderRule::Explicit(names)) => {
+                let mut positions = HashMap::new();
+                for (i, name_path) in names.into_iter().enumerate() {
+                    check_nested(name_path.is_nested(), recursive, name_path.span())?;
+                    named.push((name_path.to_string(), name_path.span()));
+                    if positions.insert(name_path.to_string(), i).is_some() {
+                        return Err(Error::new(
+                            name_path.span(),
+                            "The module is ordered multiple times",
+                        ));
+                    }
+                }
+                Self::Explicit(positions)
+            }
+        })
+    }
+
+    /// Compares the names of two modules in the inline module `prefix`
+    fn compare(&self, prefix: &str, a: &str, b: &str) -> Ordering {
+        match self {
+            Self::Alphabetical => a.cmp(b),
+            Self::Natural => natural_cmp(a, b),
+            Self::Explicit(positions) => {
+                let pa = positions.get(&format!("{}{}", prefix, a));
+                let pb = positions.get(&format!("{}{}", prefix, b));
+                match (pa, pb) {
+                    (Some(pa), Some(pb)) => pa.cmp(pb),
+                    (Some(_), None) => Ordering::Less,
+                    (None, Some(_)) => Ordering::Greater,
+                    (None, None) => a.cmp(b),
+                }
+            }
+        }
+    }
+}
+
+/// Compares two strings, treating digit sequences as numbers, e.g. `2_x` < `10_x`
+fn natural_cmp(a: &str, b: &str) -> Ordering {
+    fn chunks(s: &str) -> Vec<(bool, &str)> {
+        let mut ret = vec![];
+        let mut start = 0;
+        let mut digit = None;
+        for (i, c) in s.char_indices() {
+            let is_digit = c.is_ascii_digit();
+            if digit.is_some() && digit != Some(is_digit) {
+                ret.push((digit == Some(true), &s[start..i]));
+                start = i;
+            }
+            digit = Some(is_digit);
+        }
+        if start < s.len() {
+            ret.push((digit == Some(true), &s[start..]));
+        }
+        ret
+    }
+
+    for (x, y) in chunks(a).into_iter().zip(chunks(b)) {
+        let ord = match (x, y) {
+            ((true, x), (true, y)) => {
+                let x = x.trim_start_matches('0');
+                let y = y.trim_start_matches('0');
+                x.len().cmp(&y.len()).then_with(|| x.cmp(y))
+            }
+            ((_, x), (_, y)) => x.cmp(y),
+        };
+        if ord != Ordering::Equal {
+            return ord;
+        }
+    }
+    // equal prefixes, e.g. `a1` and `a1b`, or `01` and `1`
+    chunks(a).len().cmp(&chunks(b).len()).then_with(|| a.cmp(b))
+}
+
+/// Declares `modules` and their inline submodules.
+///
+/// `prefix` is the `::`-terminated path of the inline module containing `modules`,
+/// or empty for the top level.
+/// `selected` is whether the inline module is selected by `only`.
+/// `base` is the directory that `#[path]` attributes are resolved against,
+/// or `None` if they must be absolute.
+fn declare_all(
+    modules: &[Module],
+    prefix: &str,
+    selected: bool,
+    options: &AllOptions,
+    base: Option<&Path>,
+) -> Result<TokenStream> {
+    let mut entries = modules
+        .iter()
+        .map(|module| {
+            let (ni, renamed) = module_ident(&module.name, &options.rename)?;
+            Ok((ni.unraw().to_string(), ni, renamed, module))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    entries.sort_by(|a, b| options.order.compare(prefix, &a.0, &b.0));
+
+    // variants of the same module, e.g. `foo.rs` and `foo.unix.rs`, are adjacent after sorting
+    let mut groups = Vec::<Vec<(String, syn::Ident, bool, &Module)>>::new();
+    for entry in entries {
+        match groups.last_mut() {
+            Some(group) if group[0].0 == entry.0 => group.push(entry),
+            _ => groups.push(vec![entry]),
+        }
+    }
+
+    let mods = groups
+        .into_iter()
+        .map(|group| -> Result<TokenStream> {
+            let name = &format!("{}{}", prefix, group[0].0);
+            let selected = selected || options.only.as_ref().is_some_and(|only| only.matches(name));
+
+            let mut modifier = match options.vis.resolve(name)? {
+                Resolution::Excluded => return Ok(quote!()),
+                Resolution::Default => match group[0].3.ty {
+                    ModuleType::File => &options.default_file,
+                    ModuleType::Dir => &options.default_dir,
+                },
+                Resolution::Special(modifier) => modifier,
+            }
+            .clone();
+            let cfgs = options
+                .cfgs
+                .iter()
+                .filter(|(names, _)| names.matches(name))
+                .map(|(_, meta)| quote!(#[cfg(#meta)]));
+            let mut meta = options
+                .meta
+                .iter()
+                .cloned()
+                .chain(cfgs)
+                .collect::<TokenStream>();
+            if options.tests.matches(name) {
+                meta.extend(quote!(#[cfg(test)]));
+                // test-only items are never re-exported
+                if modifier.imports.take().is_some() {
+                    modifier.vis = parse::PrivVis::Priv(syn::token::Priv(Span::call_site()));
+                }
+            }
+
+            let suffixes = group
+                .iter()
+                .map(|(_, _, _, module)| module.cfg.as_deref())
+                .collect::<Vec<_>>();
+            let cfgs = cfg_variants(&suffixes);
+
+            let mut stmts = Vec::with_capacity(group.len());
+            for ((_, ni, renamed, module), cfg) in group.into_iter().zip(cfgs) {
+                let path = match &module.children {
+                    Some(children) => {
+                        // paths in an inline module are resolved against its directory
+                        let child_base = base.map(|_| &*module.path);
+                        let prefix = format!("{}::", name);
+                        let contents =
+                            declare_all(children, &prefix, selected, options, child_base)?;
+                        if !selected && contents.is_empty() {
+                            continue;
+                        }
+                        ModPath::Inline(
+                            contents,
+                            if renamed {
+                                Some(path_attr(&module.path, base)?)
+                            } else {
+                                None
+                            },
+                        )
+                    }
+                    None if !selected => continue,
+                    None => module.mod_path(base, renamed)?,
+                };
+                let meta = match cfg {
+                    Some(cfg) => quote!(#[cfg(#cfg)] #meta),
+                    None => meta.clone(),
+                };
+                stmts.push(apply_modifier(&modifier, ni, &path, Some(meta)));
+            }
+            Ok(quote!(#(#stmts)*))
+        })
+        .collect::<Result<Vec<TokenStream>>>()?;
+
+    let q = quote!(#(#mods)*);
+    Ok(q)
+}
+
+pub fn os(ts: TokenStream, ctx: &mut Context) -> Result<TokenStream> {
+    let known = Known::Targets(targets::OSES);
+    cfg_args(parse_ts(ts)?, ctx, "target_os", Rename::Error, known)
+}
+
+pub fn family(ts: TokenStream, ctx: &mut Context) -> Result<TokenStream> {
+    let known = Known::Targets(targets::FAMILIES);
+    cfg_args(parse_ts(ts)?, ctx, "target_family", Rename::Error, known)
+}
+
+pub fn feature(ts: TokenStream, ctx: &mut Context) -> Result<TokenStream> {
+    let args = parse_ts(ts)?;
+    let manifest_dir = std::env::var_os("CARGO_MANIFEST_DIR")
+        .ok_or_else(|| Error::new(Span::call_site(), "CARGO_MANIFEST_DIR is not set"))?;
+    let manifest = Path::new(&manifest_dir).join("Cargo.toml");
+    let features = std::fs::read_to_string(&manifest)
+        .map_err(|err| err.to_string())
+        .and_then(|content| declared_features(&content).map_err(|err| err.to_string()))
+        .map_err(|err| {
+            Error::new(
+                Span::call_site(),
+                format!("Cannot read features from {}: {}", manifest.display(), err),
+            )
+        })?;
+    ctx.deps.push(manifest);
+    let known = Known::Features(features);
+    cfg_args(args, ctx, "feature", Rename::Error, known)
+}
+
+pub fn cfg_key(ts: TokenStream, ctx: &mut Context) -> Result<TokenStream> {
+    let args: parse::cfg::KeyedArgs = parse_ts(ts)?;
+    // values like `64` for `target_pointer_width` are common
+    let rename = Rename::Sanitize("_".to_string());
+    cfg_args(args.args, ctx, &args.key.to_string(), rename, Known::Any)
+}
+
+/// The values accepted by the cfg key of a cfg macro
+enum Known<'t> {
+    /// Any module name is used as the value as-is
+    Any,
+    /// Module names must be one of the known target values or listed in the `custom` statement
+    Targets(&'t [&'t str]),
+    /// Module names are mapped to the features declared in Cargo.toml
+    Features(Vec<String>),
+}
+
+/// Lists the features declared in the Cargo.toml `manifest`,
+/// including the implicit features of optional dependencies.
+fn declared_features(manifest: &str) -> std::result::Result<Vec<String>, toml::de::Error> {
+    let manifest: toml::Value = toml::from_str(manifest)?;
+    let table = manifest.get("features").and_then(toml::Value::as_table);
+    let mut features = table
+        .map(|table| table.keys().cloned().collect::<Vec<_>>())
+        .unwrap_or_default();
+
+    // optional dependencies referenced by `dep:` do not have an implicit feature
+    let dep_refs = table
+        .into_iter()
+        .flat_map(|table| table.values())
+        .filter_map(toml::Value::as_array)
+        .flatten()
+        .filter_map(toml::Value::as_str)
+        .filter_map(|value| value.strip_prefix("dep:"))
+        .collect::<HashSet<_>>();
+    let targets = manifest.get("target").and_then(toml::Value::as_table);
+    let platforms = std::iter::once(&manifest).chain(targets.into_iter().flat_map(|t| t.values()));
+    for platform in platforms {
+        for key in &["dependencies", "build-dependencies"] {
+            let deps = platform.get(key).and_then(toml::Value::as_table);
+            for (name, dep) in deps.into_iter().flatten() {
+                let optional = dep.get("optional").and_then(toml::Value::as_bool) == Some(true);
+                if optional && !dep_refs.contains(&**name) && !features.contains(name) {
+                    features.push(name.clone());
+                }
+            }
+        }
+    }
+    Ok(features)
+}
+
+/// Removes the disabled-feature stubs, i.e. `foo.disabled.rs` and `disabled/foo.rs`,
+/// from `modules` and returns them by the name of the feature module.
+fn take_stubs(ctx: &mut Context, modules: &mut Vec<Module>) -> Result<HashMap<String, Module>> {
+    let mut stubs = Vec::new();
+    for mut module in std::mem::take(modules) {
+        if module.name == "disabled" {
+            return Err(Error::new(
+                Span::call_site(),
+                format!(
+                    "{} cannot be a feature module because `disabled/` contains \
+                     disabled-feature stubs",
+                    module.path.display()
+                ),
+            ));
+        }
+        match module.name.strip_suffix(".disabled") {
+            Some(name) => {
+                module.name = name.to_string();
+                // `foo.disabled.rs` is declared as `foo`, so it does not own `foo.disabled/`
+                module.ty = ModuleType::File;
+                module.explicit_path = true;
+                stubs.push(module);
+            }
+            None => modules.push(module),
+        }
+    }
+
+    let dir = ctx
+        .dir
+        .as_ref()
+        .expect("the directory was listed")
+        .join("disabled");
+    if dir.is_dir() {
+        for mut stub in list_dir(&dir, None, false, &mut ctx.deps)? {
+            stub.explicit_path = true;
+            stubs.push(stub);
+        }
+    }
+
+    let mut ret = HashMap::new();
+    for stub in stubs {
+        if let Some(other) = ret.insert(stub.name.clone(), stub) {
+            return Err(Error::new(
+                Span::call_site(),
+                format!(
+                    "The disabled-feature stub of `{}` is declared by both {} and {}",
+                    other.name,
+                    other.path.display(),
+                    ret[&other.name].path.display()
+                ),
+            ));
+        }
+    }
+    Ok(ret)
+}
+
+/// Maps each module name to the feature it is compiled with.
+///
+/// Features may contain hyphens, which are replaced by underscores in module names.
+fn feature_values(modules: &[Module], features: &[String]) -> Result<Vec<String>> {
+    let mut values = Vec::new();
+    let mut errors = Vec::new();
+    for module in modules {
+        let name = &module.name;
+        if features.contains(name) {
+            values.push(name.clone());
+            continue;
+        }
+        let matches = features
+            .iter()
+            .filter(|feature| feature.replace('-', "_") == *name)
+            .collect::<Vec<_>>();
+        match matches[..] {
+            [feature] => values.push(feature.clone()),
+            [] => {
+                let mut msg = format!("`{}` is not a feature declared in Cargo.toml", name);
+                let candidates = features.iter().map(|feature| &feature[..]);
+                if let Some(suggestion) = suggest(name, candidates) {
+                    msg += &format!("; did you mean `{}`?", suggestion);
+                }
+                errors.push(Error::new(Span::call_site(), msg));
+            }
+            _ => {
+                let matches = matches
+                    .iter()
+                    .map(|feature| format!("`{}`", feature))
+                    .collect::<Vec<_>>();
+                let msg = format!("`{}` matches the features {}", name, matches.join(", "));
+                errors.push(Error::new(Span::call_site(), msg));
+            }
+        }
+    }
+    combine_errors(errors)?;
+    Ok(values)
+}
+
+/// The parsed arguments of a cfg macro and the modules to declare
+struct CfgModules {
+    modifier: parse::Modifier,
+    fallback: Option<parse::Fallback>,
+    rename: Rename,
+    /// The modules, sorted by the `order` statement, excluding the fallback module
+    modules: Vec<Module>,
+    /// The cfg values of `modules`, which differ from the module names for hyphenated features
+    values: Vec<String>,
+    /// The disabled-feature stubs of `modules`, declared when the cfg value is disabled
+    stubs: Vec<Option<Module>>,
+    /// The module named by `|| $module`
+    fallback_module: Option<Module>,
+    /// The modules with their own visibility, declared unconditionally
+    common: Vec<(Module, parse::Modifier)>,
+    /// The `as $alias` statement
+    alias: Option<parse::ArgAlias>,
+    /// The `exclusive` statement
+    exclusive: Option<parse::ArgExclusive>,
+}
+
+impl CfgModules {
+    /// Parses the arguments and lists the modules.
+    ///
+    /// `default_rename` is used if there is no `rename` statement.
+    /// `known` determines the cfg value of each module.
+    fn new(
+        args: parse::cfg::Args,
+        ctx: &mut Context,
+        flag_name: &str,
+        default_rename: Rename,
+        known: Known,
+    ) -> Result<Self> {
+        let (single, (customs, sv, excepts)) = parse_args! {
+            args, cfg;
+            single: Cfg, DefaultVis, Alias, Exclusive, Rename, In, Order;
+            multi: Custom, SpecialVis, Except;
+        };
+        let (arg, default_vis, alias, exclusive, rename, arg_in, order) = single;
+        if let Some(arg_in) = &arg_in {
+            ctx.relocate(arg_in)?;
+        }
+        let rename = match rename {
+            Some(rename) => Rename::from_arg(Some(rename)),
+            None => default_rename,
+        };
+        let mut named = Vec::new();
+        let order = Order::from_arg(order, false, &mut named)?;
+
+        let vis = VisOptions::new(sv, excepts, &mut |pattern| {
+            if pattern.is_nested() {
+                return Err(Error::new(
+                    pattern.span(),
+                    "Nested module paths cannot be used in cfg macros",
+                ));
+            }
+            if let parse::ModulePattern::Path(path) = pattern {
+                named.push((path.to_string(), path.span()));
+            }
+            Pattern::new(pattern)
+        })?;
+
+        let mut custom = HashSet::new();
+        for custom_arg in customs {
+            if !matches::matches!(known, Known::Targets(_)) {
+                return Err(Error::new(
+                    custom_arg.span(),
+                    "The `custom` statement is only supported by `os!` and `family!`",
+                ));
+            }
+            for name in custom_arg.names {
+                named.push((name.to_string(), name.span()));
+                custom.insert(name.to_string());
+            }
+        }
+
+        let mut modules = list_mods(ctx, false).map_err(crate::context("directory listing"))?;
+        if let Some(module) = modules.iter().find(|module| module.cfg.is_some()) {
+            return Err(Error::new(
+                Span::call_site(),
+                format!(
+                    "{} has a target suffix, which is only supported by `all!`",
+                    module.path.display()
+                ),
+            ));
+        }
+        let mut stubs = match known {
+            Known::Features(_) => take_stubs(ctx, &mut modules)?,
+            _ => HashMap::new(),
+        };
+        resolve_conflicts(&mut modules, "", &rename, &mut HashMap::new())?;
+        let mut paths = Vec::new();
+        collect_paths(&modules, "", &rename, &mut paths)?;
+        check_names(&named, &paths)?;
+
+        let (modifier, fallback) = match (arg, default_vis) {
+            (Some(_), Some(default_vis)) => {
+                return Err(Error::new(
+                    default_vis.span(),
+                    "The visibility of the cfg modules is specified twice",
+                ))
+            }
+            (Some(arg), None) => (arg.modifier, arg.fallback.map(|(_, fallback)| fallback)),
+            (None, Some(default_vis)) => {
+                if !matches::matches!(default_vis.module_type, parse::ModuleTypeKw::All) {
+                    return Err(Error::new(
+                        default_vis.span(),
+                        "`default file` and `default dir` cannot be used in cfg macros",
+                    ));
+                }
+                (default_vis.modifier, None)
+            }
+            (None, None) => (parse::Modifier::default_cfg(), None),
+        };
+        let fallback_module = match &fallback {
+            Some(parse::Fallback::Module(ident)) => {
+                let name = ident.unraw().to_string();
+                check_names(&[(name.clone(), ident.span())], &paths)?;
+                let index = paths
+                    .iter()
+                    .position(|path| *path == name)
+                    .expect("fallback module was checked to exist");
+                Some(modules.remove(index))
+            }
+            _ => None,
+        };
+
+        let mut common = Vec::new();
+        let mut cfg_modules = Vec::new();
+        for module in modules {
+            let (ident, _) = module_ident(&module.name, &rename)?;
+            match vis.resolve(&ident.unraw().to_string())? {
+                Resolution::Excluded => {
+                    stubs.remove(&module.name);
+                }
+                Resolution::Default => cfg_modules.push(module),
+                Resolution::Special(modifier) => common.push((module, modifier.clone())),
+            }
+        }
+        let mut modules = cfg_modules;
+
+        modules.sort_by(|a, b| order.compare("", &a.name, &b.name));
+        common.sort_by(|(a, _), (b, _)| order.compare("", &a.name, &b.name));
+        let values = match &known {
+            Known::Any => modules.iter().map(|module| module.name.clone()).collect(),
+            Known::Targets(known) => {
+                check_known(&modules, flag_name, known, &custom)?;
+                modules.iter().map(|module| module.name.clone()).collect()
+            }
+            Known::Features(features) => feature_values(&modules, features)?,
+        };
+        let module_stubs = modules
+            .iter()
+            .map(|module| stubs.remove(&module.name))
+            .collect();
+        if let Some((name, stub)) = stubs.into_iter().next() {
+            return Err(Error::new(
+                Span::call_site(),
+                format!(
+                    "{} is a disabled-feature stub, but `{}` is not a feature module",
+                    stub.path.display(),
+                    name
+                ),
+            ));
+        }
+
+        Ok(Self {
+            modifier,
+            fallback,
+            rename,
+            modules,
+            values,
+            stubs: module_stubs,
+            fallback_module,
+            common,
+            alias,
+            exclusive,
+        })
+    }
+
+    /// Declares the modules with their own visibility
+    fn declare_common(&self, ctx: &Context) -> Result<TokenStream> {
+        self.common
+            .iter()
+            .map(|(module, modifier)| {
+                let (ident, renamed) = module_ident(&module.name, &self.rename)?;
+                Ok(apply_modifier(
+                    modifier,
+                    ident,
+                    &module.mod_path(ctx.path_base(), renamed)?,
+                    None,
+                ))
+            })
+            .collect()
+    }
+}
+
+/// Checks that at most one of the values `mods` of the cfg key `flag` is enabled
+fn declare_conflicts(flag: &syn::Ident, mods: &[String]) -> TokenStream {
+    mods.iter()
+        .enumerate()
+        .flat_map(|(i, a)| mods[(i + 1)..].iter().map(move |b| (a, b)))
+        .map(|(a, b)| {
+            let error = format!(
+                "`{} = \"{}\"` and `{} = \"{}\"` are mutually exclusive",
+                flag, a, flag, b
+            );
+            quote! {
+                #[cfg(all(#flag = #a, #flag = #b))]
+                compile_error!(#error);
+            }
+        })
+        .collect()
+}
+
+/// Declares the `as $alias` statement for `variants`,
+/// which are the `cfg` predicates, identifiers and names of the modules.
+///
+/// Several predicates may hold at once, e.g. for two enabled features,
+/// in which case the earliest module is selected.
+fn declare_alias(
+    alias: Option<&parse::ArgAlias>,
+    variants: &[(TokenStream, syn::Ident, String)],
+) -> TokenStream {
+    let alias = match alias {
+        Some(alias) => alias,
+        None => return quote!(),
+    };
+    let vis = &alias.vis;
+    let alias_ident = &alias.alias;
+    let doc = format!("The name of the module selected as `{}`", alias_ident);
+    let mut earlier = Vec::new();
+    variants
+        .iter()
+        .map(|(pred, ident, name)| {
+            let selected = quote!(all(#pred, not(any(#(#earlier),*))));
+            earlier.push(pred);
+            quote! {
+                #[cfg(#selected)]
+                #vis use self::#ident as #alias_ident;
+                #[cfg(#selected)]
+                #[doc = #doc]
+                #[allow(dead_code)]
+                #vis const SELECTED: &str = #name;
+            }
+        })
+        .collect()
+}
+
+/// Reports an error for each module that is neither a known value of `flag_name` nor `custom`
+fn check_known(
+    modules: &[Module],
+    flag_name: &str,
+    known: &[&str],
+    custom: &HashSet<String>,
+) -> Result<()> {
+    let errors = modules
+        .iter()
+        .filter(|module| !known.contains(&&*module.name) && !custom.contains(&module.name))
+        .map(|module| {
+            // case differences like `macOS` are too large for the edit distance threshold
+            let suggestion = suggest(&module.name.to_lowercase(), known.iter().copied());
+            let mut msg = format!("`{}` is not a known `{}` value", module.name, flag_name);
+            match suggestion {
+                Some(suggestion) => msg += &format!("; did you mean `{}`?", suggestion),
+                None => msg += ".",
+            }
+            msg += &format!(
+                " Add `custom {}` if it is the name of a custom target",
+                module.name
+            );
+            Error::new(Span::call_site(), msg)
+        });
+    combine_errors(errors)
+}
+
+/// Declares the modules in the directory conditionally by the cfg key `flag_name`.
+///
+/// `default_rename` is used if there is no `rename` statement.
+/// `known` is passed to [`CfgModules::new`].
+fn cfg_args(
+    args: parse::cfg::Args,
+    ctx: &mut Context,
+    flag_name: &str,
+    default_rename: Rename,
+    known: Known,
+) -> Result<TokenStream> {
+    let flag = syn::Ident::new(flag_name, Span::call_site());
+
+    let cfg_modules = CfgModules::new(args, ctx, flag_name, default_rename, known)?;
+    let common = cfg_modules.declare_common(ctx)?;
+    let CfgModules {
+        modifier,
+        fallback,
+        rename,
+        modules,
+        values: mods,
+        stubs,
+        fallback_module,
+        alias,
+        exclusive,
+        ..
+    } = cfg_modules;
+    let mut variants = Vec::new();
+    let mods_code = modules
+        .iter()
+        .zip(&mods)
+        .zip(&stubs)
+        .map(|((module, value), stub)| {
+            let name = &module.name;
+            let (ident, renamed) = module_ident(name, &rename)?;
+            let pred = quote!(#flag = #value);
+            variants.push((pred.clone(), ident.clone(), name.clone()));
+            let stub = match stub {
+                Some(stub) => apply_modifier(
+                    &modifier,
+                    ident.clone(),
+                    &stub.mod_path(ctx.path_base(), renamed)?,
+                    Some(quote!(#[cfg(not(#pred))])),
+                ),
+                None => quote!(),
+            };
+            let module = apply_modifier(
+                &modifier,
+                ident,
+                &module.mod_path(ctx.path_base(), renamed)?,
+                Some(quote!(#[cfg(#pred)])),
+            );
+            Ok(quote!(#module #stub))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let conflicts = if exclusive.is_some() {
+        declare_conflicts(&flag, &mods)
+    } else {
+        quote!()
+    };
+    // `exclusive` implies `||` to check that at least one module is compiled
+    let fallback = match fallback {
+        None if exclusive.is_some() => Some(parse::Fallback::Error(None)),
+        fallback => fallback,
+    };
+    let el = match fallback {
+        None => quote!(),
+        Some(parse::Fallback::Module(_)) => {
+            let module = fallback_module.expect("fallback module was checked to exist");
+            let (ident, renamed) = module_ident(&module.name, &rename)?;
+            let pred = quote!(not(any(#(#flag = #mods),*)));
+            variants.push((pred.clone(), ident.clone(), module.name.clone()));
+            apply_modifier(
+                &modifier,
+                ident,
+                &module.mod_path(ctx.path_base(), renamed)?,
+                Some(quote!(#[cfg(#pred)])),
+            )
+        }
+        Some(parse::Fallback::Error(error)) => {
+            let error = error.as_ref().map_or(
+                format!("{} must be one of \"{}\"", flag, mods.join("\", \"")),
+                |error| error.value(),
+            );
+            quote! {
+                #[cfg(not(any(#(#flag = #mods),*)))]
+                compile_error!(#error);
+            }
+        }
+    };
+
+    let alias_code = declare_alias(alias.as_ref(), &variants);
+
+    let ret = quote! {
+        #common
+        #(#mods_code)*
+        #el
+        #conflicts
+        #alias_code
+    };
+    Ok(ret)
+}
+
+pub fn cfg_flag(ts: TokenStream, ctx: &mut Context) -> Result<TokenStream> {
+    let cfg_modules = CfgModules::new(parse_ts(ts)?, ctx, "cfg", Rename::Error, Known::Any)?;
+    let common = cfg_modules.declare_common(ctx)?;
+    let CfgModules {
+        modifier,
+        fallback,
+        rename,
+        modules,
+        fallback_module,
+        alias,
+        exclusive,
+        ..
+    } = cfg_modules;
+    if let Some(exclusive) = exclusive {
+        return Err(Error::new(
+            exclusive.span(),
+            "`cfg_flag!` with `||` already compiles only one module; use it instead of `exclusive`",
+        ));
+    }
+
+    let mut variants = Vec::new();
+    let mut flags = Vec::<syn::Ident>::new();
+    let mut mods_code = Vec::new();
+    for module in &modules {
+        let (ident, renamed) = module_ident(&module.name, &rename)?;
+        let path = module.mod_path(ctx.path_base(), renamed)?;
+
+        let flag = match syn::parse_str::<syn::Ident>(&module.name) {
+            Ok(flag) if !renamed => flag,
+            _ => {
+                return Err(Error::new(
+                    Span::call_site(),
+                    format!("`{}` is not a valid cfg flag name", module.name),
+                ))
+            }
+        };
+        // with `||`, earlier modules take priority so that exactly one module is compiled
+        let pred = if fallback.is_some() {
+            quote!(all(#flag, not(any(#(#flags),*))))
+        } else {
+            quote!(#flag)
+        };
+        variants.push((pred.clone(), ident.clone(), module.name.clone()));
+        let meta = quote!(#[cfg(#pred)]);
+        mods_code.push(apply_modifier(&modifier, ident, &path, Some(meta)));
+        flags.push(flag);
+    }
+
+    let el = match fallback {
+        None => quote!(),
+        Some(parse::Fallback::Module(_)) => {
+            let module = fallback_module.expect("fallback module was checked to exist");
+            let (ident, renamed) = module_ident(&module.name, &rename)?;
+            let pred = quote!(not(any(#(#flags),*)));
+            variants.push((pred.clone(), ident.clone(), module.name.clone()));
+            apply_modifier(
+                &modifier,
+                ident,
+                &module.mod_path(ctx.path_base(), renamed)?,
+                Some(quote!(#[cfg(#pred)])),
+            )
+        }
+        Some(parse::Fallback::Error(error)) => {
+            let names = flags
+                .iter()
+                .map(|flag| format!("`{}`", flag))
+                .collect::<Vec<_>>();
+            let error = error.map_or_else(
+                || format!("One of the cfg flags {} must be enabled", names.join(", ")),
+                |error| error.value(),
+            );
+            quote! {
+                #[cfg(not(any(#(#flags),*)))]
+                compile_error!(#error);
+            }
+        }
+    };
+
+    let alias_code = declare_alias(alias.as_ref(), &variants);
+
+    Ok(quote! {
+        #common
+        #(#mods_code)*
+        #el
+        #alias_code
+    })
+}
+
+/// The directory scanned by a macro call
+#[derive(Clone, Debug)]
+pub struct Context {
+    /// The directory containing the modules to declare,
+    /// or `None` if the calling file cannot be located and `in crate` must be used
+    pub dir: Option<PathBuf>,
+    /// The directory of the calling file, against which relative `in` paths are resolved
+    pub base: Option<PathBuf>,
+    /// A file in `dir` that should not be declared, usually the calling file itself
+    pub exclude: Option<OsString>,
+    /// Whether every module should be declared with an absolute `#[path]`,
+    /// e.g. when the generated code is `include!`d from another directory
+    pub explicit_paths: bool,
+    /// The paths that the generated code depends on, collected during module discovery
+    pub deps: Vec<PathBuf>,
+}
+
+impl Context {
+    /// Creates a context for the module directory owned by the source file at `path`.
+    ///
+    /// mod.rs, lib.rs, main.rs and other crate roots like `src/bin/tool.rs`
+    /// own their parent directory, while other files like foo.rs own the foo/ directory
+    /// next to them.
+    pub fn for_file(path: &Path) -> Result<Self> {
+        let parent = path
+            .parent()
+            .ok_or_else(|| Error::new(Span::call_site(), "parent directory does not exist"))?;
+        let stem = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .ok_or_else(|| {
+                Error::new(Span::call_site(), "Source file name is not UTF-8 compliant")
+            })?;
+        let (dir, exclude) = match stem {
+            "mod" | "lib" | "main" => (parent.to_path_buf(), path.file_name().map(Into::into)),
+            _ if is_crate_root(parent, stem) => {
+                (parent.to_path_buf(), path.file_name().map(Into::into))
+            }
+            _ => (parent.join(stem), None),
+        };
+        Ok(Self {
+            dir: Some(dir),
+            base: Some(parent.to_path_buf()),
+            exclude,
+            explicit_paths: false,
+            deps: vec![],
+        })
+    }
+
+    /// Creates a context for a macro call whose file cannot be located,
+    /// e.g. when invoked from another macro.
+    pub fn unlocated() -> Self {
+        Self {
+            dir: None,
+            base: None,
+            exclude: None,
+            explicit_paths: true,
+            deps: vec![],
+        }
+    }
+
+    /// The directory that `#[path]` attributes in the generated code are resolved against,
+    /// i.e. the directory of the calling file,
+    /// or `None` if every module must be declared with an absolute `#[path]`.
+    fn path_base(&self) -> Option<&Path> {
+        if self.explicit_paths {
+            None
+        } else {
+            self.base.as_deref()
+        }
+    }
+
+    /// Moves the scanned directory to the path given in an `in` statement
+    fn relocate(&mut self, arg: &parse::ArgIn) -> Result<()> {
+        let env_dir = |var| {
+            std::env::var_os(var)
+                .map(PathBuf::from)
+                .ok_or_else(|| Error::new(arg.span(), format!("{} is not set", var)))
+        };
+        let manifest_dir = env_dir("CARGO_MANIFEST_DIR")?;
+        let base = match &arg.base {
+            parse::InBase::File => self.base.clone().ok_or_else(|| {
+                Error::new(
+                    arg.span(),
+                    "The calling file cannot be located; use `in crate \"...\"` instead",
+                )
+            })?,
+            parse::InBase::Crate(_) => manifest_dir.clone(),
+            parse::InBase::OutDir(_) => env_dir("OUT_DIR")?,
+        };
+
+        let dir = base.join(arg.path.value());
+        let dir = dir.canonicalize().map_err(|err| {
+            Error::new(
+                arg.path.span(),
+                format!("Cannot open {}: {}", dir.display(), err),
+            )
+        })?;
+        // OUT_DIR is usually outside the workspace, so only the directory itself is allowed
+        let root = match &arg.base {
+            parse::InBase::OutDir(_) => base,
+            _ => workspace_root(&manifest_dir),
+        };
+        let root = root.canonicalize().unwrap_or(root);
+        if !dir.starts_with(&root) {
+            return Err(Error::new(
+                arg.path.span(),
+                format!("{} is outside {}", dir.display(), root.display()),
+            ));
+        }
+
+        self.dir = Some(dir);
+        // the calling file is not in `dir`; a mod.rs in `dir` is skipped by `list_dir` anyway
+        self.exclude = None;
+        // `#[path]` attributes are relative to the calling file, not the scanned directory
+        self.explicit_paths = true;
+        Ok(())
+    }
+}
+
+/// Checks whether `stem.rs` in `parent` is the root of the crate being compiled,
+/// e.g. `src/bin/tool.rs`, `examples/tool.rs` or `tests/tool.rs`.
+fn is_crate_root(parent: &Path, stem: &str) -> bool {
+    // a module file named after the crate is declared by the crate root next to it
+    if ["lib.rs", "main.rs", "mod.rs"]
+        .iter()
+        .any(|root| parent.join(root).is_file())
+    {
+        return false;
+    }
+    let bin_name = std::env::var("CARGO_BIN_NAME").ok();
+    let crate_name = std::env::var("CARGO_CRATE_NAME").ok();
+    bin_name.as_deref() == Some(stem) || crate_name == Some(stem.replace('-', "_"))
+}
+
+/// Finds the root of the workspace containing the package at `manifest_dir`,
+/// i.e. the nearest ancestor with a `[workspace]` table in its Cargo.toml.
+fn workspace_root(manifest_dir: &Path) -> PathBuf {
+    manifest_dir
+        .ancestors()
+        .find(|dir| {
+            std::fs::read_to_string(dir.join("Cargo.toml"))
+                .ok()
+                .and_then(|manifest| manifest.parse::<toml::Value>().ok())
+                .is_some_and(|manifest| manifest.get("workspace").is_some())
+        })
+        .unwrap_or(manifest_dir)
+        .to_path_buf()
+}
+
+/// Lists the modules in the directory of `ctx`.
+///
+/// In recursive mode, subdirectories that are not modules by themselves
+/// (i.e. without mod.rs or a sibling .rs file) are listed as inline modules.
+fn list_mods(ctx: &mut Context, recursive: bool) -> Result<Vec<Module>> {
+    let dir = ctx.dir.clone().ok_or_else(|| {
+        Error::new(
+            Span::call_site(),
+            "The calling file cannot be located, e.g. because dirmod is invoked from another \
+             macro; use `in crate \"...\"` to specify the directory",
+        )
+    })?;
+    let exclude = ctx.exclude.clone();
+    ctx.deps.push(dir.clone());
+    let mut modules = list_dir(&dir, exclude.as_deref(), recursive, &mut ctx.deps)?;
+    // crate roots are never modules of their own directory,
+    // e.g. when the directory is scanned through `in` or dirmod-build
+    modules.retain(|module| {
+        let name = module.path.file_name().and_then(OsStr::to_str);
+        !matches::matches!(name, Some("lib.rs") | Some("main.rs"))
+    });
+    Ok(modules)
+}
+
+fn list_dir(
+    dir: &Path,
+    exclude: Option<&OsStr>,
+    recursive: bool,
+    deps: &mut Vec<PathBuf>,
+) -> Result<Vec<Module>> {
+    fn me<T: std::fmt::Display>(err: T) -> Error {
+        Error::new(proc_macro2::Span::call_site(), err)
+    }
+
+    macro_rules! mes {
+        ($fmt:literal) => {
+            |err| me(format!($fmt, err))
+        };
+    }
+
+    let entries = dir.read_dir().map_err(|err| {
+        me(format!(
+            "error reading module directory {}: {}",
+            dir.display(),
+            err
+        ))
+    })?;
+    let mut ret = vec![];
+    for entry in entries {
+        let entry = entry.map_err(mes!("error reading dir entry: {}"))?;
+        let path = entry.path();
+        let ft = entry
+            .file_type()
+            .map_err(mes!("error checking dir entry file type: {}"))?;
+        // Subdirectories are also dependencies since their mod.rs may be added or removed.
+        if ft.is_dir() {
+            deps.push(path.clone());
+        }
+        // mod.rs declares the directory itself, e.g. when it `include!`s the generated code
+        if ft.is_file()
+            && path.extension().and_then(|str| str.to_str()) == Some("rs")
+            && path.file_name() != Some(OsStr::new("mod.rs"))
+            && (exclude.is_none() || path.file_name() != exclude)
+        {
+            let name = entry
+                .file_name()
+                .into_string()
+                .map_err(|_| me("Module is not UTF-8 compliant"))?;
+            let name = name[..(name.len() - 3)].to_string();
+            if let Some((base, suffix)) = split_cfg_suffix(&name) {
+                // `foo.unix.rs` is a variant of `foo`, which can only be loaded with `#[path]`
+                ret.push(Module {
+                    name: base.to_string(),
+                    ty: ModuleType::File,
+                    path,
+                    children: None,
+                    explicit_path: true,
+                    cfg: Some(suffix.to_string()),
+                });
+                continue;
+            }
+            let ty = if dir.join(&name).is_dir() {
+                ModuleType::Dir
+            } else {
+                ModuleType::File
+            };
+            ret.push(Module {
+                name,
+                ty,
+                path,
+                children: None,
+                explicit_path: false,
+                cfg: None,
+            });
+        } else if ft.is_dir() && path.join("mod.rs").is_file() {
+            let name = entry
+                .file_name()
+                .into_string()
+                .map_err(|_| me("Module is not UTF-8 compliant"))?;
+            ret.push(Module {
+                name,
+                ty: ModuleType::Dir,
+                path: path.join("mod.rs"),
+                children: None,
+                explicit_path: false,
+                cfg: None,
+            });
+        } else if ft.is_dir() && recursive {
+            let name = entry
+                .file_name()
+                .into_string()
+                .map_err(|_| me("Module is not UTF-8 compliant"))?;
+            if dir.join(format!("{}.rs", name)).is_file() {
+                // declared by the sibling file
+                continue;
+            }
+            let children = list_dir(&path, None, true, deps)?;
+            // directories without any modules, e.g. for assets, are not modules
+            if !children.is_empty() {
+                ret.push(Module {
+                    name,
+                    ty: ModuleType::Dir,
+                    path,
+                    children: Some(children),
+                    explicit_path: false,
+                    cfg: None,
+                });
+            }
+        }
+    }
+
+    // `read_dir` order depends on the file system
+    ret.sort_by(|a, b| a.name.cmp(&b.name).then_with(|| a.cfg.cmp(&b.cfg)));
+    Ok(ret)
+}
+
+/// Splits a file stem like `foo.unix` into the module name and the target suffix.
+///
+/// Only `target_family` values and the `target_os` values in [`targets::SUFFIX_OSES`]
+/// are recognized as suffixes.
+fn split_cfg_suffix(stem: &str) -> Option<(&str, &str)> {
+    let (base, suffix) = stem.split_at(stem.find('.')?);
+    let suffix = &suffix[1..];
+    if cfg_suffix_key(suffix).is_some() {
+        Some((base, suffix))
+    } else {
+        None
+    }
+}
+
+/// Returns the cfg key matched by a file name suffix.
+///
+/// Families take precedence over OS names, so `foo.windows.rs` is selected by `target_family`.
+fn cfg_suffix_key(suffix: &str) -> Option<&'static str> {
+    if targets::FAMILIES.contains(&suffix) {
+        Some("target_family")
+    } else if targets::SUFFIX_OSES.contains(&suffix) {
+        Some("target_os")
+    } else {
+        None
+    }
+}
+
+/// Computes the `cfg` predicates of the variants of a module.
+///
+/// `suffixes` are the target suffixes of the variants, with `None` for the generic `name.rs`.
+/// OS-specific variants take precedence over family-specific variants,
+/// and the generic variant is only compiled if no other variant matches.
+fn cfg_variants(suffixes: &[Option<&str>]) -> Vec<Option<TokenStream>> {
+    let key =
+        |suffix: &str| cfg_suffix_key(suffix).expect("suffix is recognized by split_cfg_suffix");
+    let pred = |suffix: &str| {
+        let key = syn::Ident::new(key(suffix), Span::call_site());
+        quote!(#key = #suffix)
+    };
+    // OS variants and families earlier in `FAMILIES` take precedence
+    let rank = |suffix: &str| match key(suffix) {
+        "target_os" => 0,
+        _ => {
+            1 + targets::FAMILIES
+                .iter()
+                .position(|family| *family == suffix)
+                .unwrap_or(0)
+        }
+    };
+    let all_preds = suffixes
+        .iter()
+        .flatten()
+        .map(|suffix| pred(suffix))
+        .collect::<Vec<_>>();
+
+    suffixes
+        .iter()
+        .map(|suffix| match suffix {
+            None if all_preds.is_empty() => None,
+            None => Some(quote!(not(any(#(#all_preds),*)))),
+            Some(suffix) => {
+                let preceding = suffixes
+                    .iter()
+                    .flatten()
+                    .filter(|other| rank(other) < rank(suffix))
+                    .map(|other| pred(other))
+                    .collect::<Vec<_>>();
+                let pred = pred(suffix);
+                if preceding.is_empty() {
+                    Some(pred)
+                } else {
+                    Some(quote!(all(#pred, not(any(#(#preceding),*)))))
+                }
+            }
+        })
+        .collect()
+}
+
+/// Resolves modules declared by both `foo.rs` and `foo/mod.rs`.
+///
+/// `prefer` maps module paths to their span and whether the directory is preferred.
+/// Used entries are removed from `prefer`.
+fn resolve_conflicts(
+    modules: &mut Vec<Module>,
+    prefix: &str,
+    rename: &Rename,
+    prefer: &mut HashMap<String, (Span, bool)>,
+) -> Result<()> {
+    let mut resolved = Vec::<Module>::with_capacity(modules.len());
+    for mut module in modules.drain(..) {
+        let (ident, _) = module_ident(&module.name, rename)?;
+        let path = format!("{}{}", prefix, ident.unraw());
+        if let Some(children) = &mut module.children {
+            resolve_conflicts(children, &format!("{}::", path), rename, prefer)?;
+        }
+
+        // modules are sorted by name, so conflicting entries are adjacent
+        let last = match resolved.last_mut() {
+            Some(last)
+                if last.name == module.name && last.cfg.is_none() && module.cfg.is_none() =>
+            {
+                last
+            }
+            _ => {
+                resolved.push(module);
+                continue;
+            }
+        };
+        let (mut file, mut dir) = if module.path.ends_with("mod.rs") {
+            (last.clone(), module)
+        } else {
+            (module, last.clone())
+        };
+        *last = match prefer.remove(&path) {
+            Some((_, true)) => {
+                dir.explicit_path = true;
+                dir
+            }
+            Some((_, false)) => {
+                file.ty = ModuleType::File;
+                file.explicit_path = true;
+                file
+            }
+            None => {
+                return Err(Error::new(
+                    Span::call_site(),
+                    format!(
+                        "The module `{}` is declared by both {} and {}; \
+                         use `prefer file {}` or `prefer dir {}` to choose one",
+                        path,
+                        file.path.display(),
+                        dir.path.display(),
+                        path,
+                        path
+                    ),
+                ))
+            }
+        };
+    }
+    *modules = resolved;
+    Ok(())
+}
+
+#[derive(Clone, Debug)]
+struct Module {
+    name: String,
+    ty: ModuleType,
+    /// The file of the module, i.e. `name.rs` or `name/mod.rs`,
+    /// or the directory of an inline module
+    path: PathBuf,
+    /// The submodules of an inline module in recursive mode
+    children: Option<Vec<Module>>,
+    /// Whether the module must be declared with `#[path]`,
+    /// e.g. when both `foo.rs` and `foo/mod.rs` exist
+    explicit_path: bool,
+    /// The target suffix of a `name.<cfg>.rs` file, e.g. `unix` for `foo.unix.rs`
+    cfg: Option<String>,
+}
+
+impl Module {
+    /// Determines how the `mod` item of a module file locates the file.
+    ///
+    /// `base` is the directory that `#[path]` attributes are resolved against,
+    /// or `None` if they must be absolute.
+    /// `renamed` indicates that the module identifier is different from the file name.
+    fn mod_path(&self, base: Option<&Path>, renamed: bool) -> Result<ModPath> {
+        if base.is_some() && !renamed && !self.explicit_path {
+            return Ok(ModPath::Implicit);
+        }
+
+        let owns_sibling_dir = self.path.file_name() != Some("mod.rs".as_ref())
+            && matches::matches!(self.ty, ModuleType::Dir);
+        if owns_sibling_dir && renamed {
+            return Err(Error::new(
+                Span::call_site(),
+                format!(
+                    "`{}.rs` cannot be declared with a different module name \
+                     because it owns the `{}/` directory",
+                    self.name, self.name
+                ),
+            ));
+        }
+        Ok(if owns_sibling_dir {
+            let parent = self
+                .path
+                .parent()
+                .expect("module file has a parent directory");
+            ModPath::Wrapped(path_attr(parent, base)?)
+        } else {
+            ModPath::File(path_attr(&self.path, base)?)
+        })
+    }
+}
+
+/// Formats `path` for a `#[path]` attribute resolved against `base`,
+/// so that the generated code does not depend on the location of the workspace.
+fn path_attr(path: &Path, base: Option<&Path>) -> Result<String> {
+    let path = match base.and_then(|base| path.strip_prefix(base).ok()) {
+        Some(path) if path.as_os_str().is_empty() => Path::new("."),
+        Some(path) => path,
+        None => path,
+    };
+    path_str(path)
+}
+
+fn path_str(path: &Path) -> Result<String> {
+    Ok(path
+        .to_str()
+        .ok_or_else(|| Error::new(Span::call_site(), "Module path is not UTF-8 compliant"))?
+        .to_string())
+}
+
+/// How module names that are not valid identifiers are handled
+#[derive(Clone, Debug)]
+enum Rename {
+    /// Report a compile error
+    Error,
+    /// Replace invalid characters with `_`, and prepend the string to names starting with a digit
+    Sanitize(String),
+}
+
+impl Rename {
+    fn from_arg(arg: Option<parse::ArgRename>) -> Self {
+        match arg.map(|arg| arg.rule) {
+            None | Some(parse::RenameRule::Error(_)) => Self::Error,
+            Some(parse::RenameRule::Sanitize(_, prefix)) => {
+                Self::Sanitize(prefix.map_or_else(|| "_".to_string(), |prefix| prefix.value()))
+            }
+        }
+    }
+}
+
+/// Converts a file name into a module identifier.
+///
+/// Keywords are converted to raw identifiers.
+/// The returned `bool` indicates whether the identifier is different from the file name,
+/// in which case the module has to be declared with `#[path]`.
+fn module_ident(name: &str, rename: &Rename) -> Result<(syn::Ident, bool)> {
+    if let Some(ident) = try_ident(name) {
+        return Ok((ident, false));
+    }
+
+    match rename {
+        Rename::Error => {
+            let mut msg = format!("`{}` is not a valid module name", name);
+            let targets = targets::FAMILIES
+                .iter()
+                .chain(targets::SUFFIX_OSES)
+                .copied();
+            if let Some((base, suffix)) = name.find('.').map(|index| name.split_at(index)) {
+                if let Some(target) = suggest(&suffix[1..], targets) {
+                    msg += &format!(
+                        ". Did you mean `{}.{}.rs` for a target-specific variant?",
+                        base, target
+                    );
+                    return Err(Error::new(Span::call_site(), msg));
+                }
+            }
+            if let Some(ident) = sanitize(name, "_") {
+                msg += &format!(
+                    ". Rename the file, or add `rename sanitize` to declare it as `{}`",
+                    ident
+                );
+            }
+            Err(Error::new(Span::call_site(), msg))
+        }
+        Rename::Sanitize(prefix) => {
+            let ident = sanitize(name, prefix).ok_or_else(|| {
+                Error::new(
+                    Span::call_site(),
+                    format!("`{}` cannot be sanitized into a valid module name", name),
+                )
+            })?;
+            Ok((ident, true))
+        }
+    }
+}
+
+/// Parses `name` as an identifier, or as a raw identifier if it is a keyword
+fn try_ident(name: &str) -> Option<syn::Ident> {
+    if matches::matches!(name, "self" | "Self" | "super" | "crate" | "_") {
+        // these keywords cannot be raw identifiers
+        None
+    } else if syn::parse_str::<syn::Ident>(name).is_ok() {
+        Some(syn::Ident::new(name, Span::call_site()))
+    } else if syn::parse_str::<syn::Ident>(&format!("r#{}", name)).is_ok() {
+        Some(syn::Ident::new_raw(name, Span::call_site()))
+    } else {
+        None
+    }
+}
+
+/// Replaces invalid characters in `name` with `_`,
+/// and prepends `prefix` if `name` starts with a digit
+fn sanitize(name: &str, prefix: &str) -> Option<syn::Ident> {
+    let mut sanitized = name
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect::<String>();
+    if sanitized.starts_with(|c: char| c.is_ascii_digit()) {
+        sanitized.insert_str(0, prefix);
+    }
+    try_ident(&sanitized)
+}
+
+/// How a generated `mod` item locates its file
+enum ModPath {
+    /// rustc resolves the file from the module name
+    Implicit,
+    /// The module is declared inline with the given contents,
+    /// with a `#[path]` pointing to its directory if the module is renamed
+    Inline(TokenStream, Option<String>),
+    /// `#[path]` points to the module file
+    File(String),
+    /// The module is declared in a wrapper module whose `#[path]` points to the parent directory.
+    ///
+    /// This is required for modules owning a sibling directory (`foo.rs` + `foo/`),
+    /// because a file loaded through `#[path]` on `mod foo;` cannot own the `foo/` directory.
+    Wrapped(String),
+}
+
+#[derive(Clone, Debug)]
+enum ModuleType {
+    File,
+    Dir,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cmp::Ordering;
+    use std::path::Path;
+
+    use quote::quote;
+
+    use super::{
+        apply_modifier, cfg_variants, check_known, collect_paths, declare_alias, declare_conflicts,
+        declared_features, feature_values, glob_to_regex, natural_cmp, path_attr, split_cfg_suffix,
+        suggest, workspace_root, Context, ModPath, Module, ModuleType, Rename,
+    };
+
+    /// Creates file modules named `names`
+    fn modules(names: &[&str]) -> Vec<Module> {
+        names
+            .iter()
+            .map(|name| Module {
+                name: name.to_string(),
+                ty: ModuleType::File,
+                path: format!("{}.rs", name).into(),
+                children: None,
+                explicit_path: false,
+                cfg: None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_glob_to_regex() {
+        let matches = |glob: &str, path: &str| {
+            regex::Regex::new(&glob_to_regex(glob))
+                .unwrap()
+                .is_match(path)
+        };
+        assert!(matches("*_test", "foo_test"));
+        assert!(!matches("*_test", "foo_test_util"));
+        assert!(matches("internal_?", "internal_a"));
+        assert!(!matches("*", "foo::bar"));
+        assert!(matches("foo::*", "foo::bar"));
+        assert!(matches("**_test", "foo::bar_test"));
+        assert!(!matches("a.b", "axb"));
+    }
+
+    #[test]
+    fn test_suggest() {
+        let candidates = ["handler", "helper", "io"];
+        assert_eq!(
+            suggest("handlr", candidates.iter().copied()),
+            Some("handler")
+        );
+        assert_eq!(suggest("helpr", candidates.iter().copied()), Some("helper"));
+        assert_eq!(suggest("oi", candidates.iter().copied()), None);
+        assert_eq!(suggest("unrelated", candidates.iter().copied()), None);
+    }
+
+    #[test]
+    fn test_natural_cmp() {
+        assert_eq!(natural_cmp("2_x", "10_x"), Ordering::Less);
+        assert_eq!(natural_cmp("10_x", "2_x"), Ordering::Greater);
+        assert_eq!(natural_cmp("a2", "a10"), Ordering::Less);
+        assert_eq!(natural_cmp("a", "a1"), Ordering::Less);
+        assert_eq!(natural_cmp("1", "01"), Ordering::Greater);
+        assert_eq!(natural_cmp("abc", "abd"), Ordering::Less);
+        assert_eq!(natural_cmp("x10y2", "x10y10"), Ordering::Less);
+        assert_eq!(natural_cmp("same", "same"), Ordering::Equal);
+    }
+
+    #[test]
+    fn test_split_cfg_suffix() {
+        assert_eq!(split_cfg_suffix("foo.unix"), Some(("foo", "unix")));
+        assert_eq!(split_cfg_suffix("foo.linux"), Some(("foo", "linux")));
+        assert_eq!(split_cfg_suffix("foo.unx"), None);
+        assert_eq!(split_cfg_suffix("config.none"), None);
+        assert_eq!(split_cfg_suffix("foo"), None);
+    }
+
+    #[test]
+    fn test_cfg_variants() {
+        let cfgs = cfg_variants(&[None, Some("linux"), Some("unix")])
+            .into_iter()
+            .map(|cfg| cfg.map(|cfg| cfg.to_string()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            cfgs,
+            vec![
+                Some(quote!(not(any(target_os = "linux", target_family = "unix"))).to_string()),
+                Some(quote!(target_os = "linux").to_string()),
+                Some(
+                    quote!(all(target_family = "unix", not(any(target_os = "linux")))).to_string()
+                ),
+            ]
+        );
+        assert!(cfg_variants(&[None])[0].is_none());
+
+        // emscripten is both `unix` and `wasm`, so only one of them may be compiled
+        let cfgs = cfg_variants(&[Some("unix"), Some("wasm")])
+            .into_iter()
+            .map(|cfg| cfg.map(|cfg| cfg.to_string()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            cfgs,
+            vec![
+                Some(
+                    quote!(all(
+                        target_family = "unix",
+                        not(any(target_family = "wasm"))
+                    ))
+                    .to_string()
+                ),
+                Some(quote!(target_family = "wasm").to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_apply_modifier_attrs() {
+        let ident = || syn::Ident::new("foo", proc_macro2::Span::call_site());
+        let modifier = syn::parse2(quote!(#[allow(dead_code)] pub #[doc(hidden)] use)).unwrap();
+        let meta = Some(quote!(#[cfg(unix)]));
+        assert_eq!(
+            apply_modifier(&modifier, ident(), &ModPath::Implicit, meta).to_string(),
+            quote! {
+                #[cfg(unix)] #[allow(dead_code)] mod foo;
+                #[cfg(unix)] #[doc(hidden)] pub use foo::*;
+            }
+            .to_string()
+        );
+
+        let modifier = syn::parse2(quote!(#[doc(hidden)] pub)).unwrap();
+        let path = ModPath::File("foo.rs".to_string());
+        assert_eq!(
+            apply_modifier(&modifier, ident(), &path, None).to_string(),
+            quote!(
+                #[doc(hidden)]
+                #[path = "foo.rs"]
+                pub mod foo;
+            )
+            .to_string()
+        );
+    }
+
+    #[test]
+    fn test_check_known() {
+        let known = ["linux", "macos", "windows"];
+        let custom = ["myos".to_string()].iter().cloned().collect();
+
+        assert!(check_known(&modules(&["linux", "myos"]), "target_os", &known, &custom).is_ok());
+
+        let err = check_known(&modules(&["linix"]), "target_os", &known, &custom).unwrap_err();
+        assert!(err.to_string().contains("did you mean `linux`?"));
+        let err = check_known(&modules(&["macOS"]), "target_os", &known, &custom).unwrap_err();
+        assert!(err.to_string().contains("did you mean `macos`?"));
+        let err = check_known(&modules(&["beos"]), "target_os", &known, &custom).unwrap_err();
+        assert!(err.to_string().contains("Add `custom beos`"));
+    }
+
+    #[test]
+    fn test_declare_conflicts() {
+        let flag = syn::Ident::new("feature", proc_macro2::Span::call_site());
+        let mods = ["a", "b", "c"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            declare_conflicts(&flag, &mods).to_string(),
+            quote! {
+                #[cfg(all(feature = "a", feature = "b"))]
+                compile_error!("`feature = \"a\"` and `feature = \"b\"` are mutually exclusive");
+                #[cfg(all(feature = "a", feature = "c"))]
+                compile_error!("`feature = \"a\"` and `feature = \"c\"` are mutually exclusive");
+                #[cfg(all(feature = "b", feature = "c"))]
+                compile_error!("`feature = \"b\"` and `feature = \"c\"` are mutually exclusive");
+            }
+            .to_string()
+        );
+        assert!(declare_conflicts(&flag, &mods[..1]).is_empty());
+    }
+
+    #[test]
+    fn test_declared_features() {
+        let manifest = r#"
+            [package]
+            name = "foo"
+
+            [features]
+            default = ["serde-json"]
+            serde-json = ["dep:serde_json"]
+
+            [dependencies]
+            serde_json = {version = "1.0", optional = true}
+            rand = {version = "0.8", optional = true}
+            regex = "1.5"
+
+            [target.'cfg(unix)'.dependencies.nix]
+            version = "0.23"
+            optional = true
+        "#;
+        let mut features = declared_features(manifest).unwrap();
+        features.sort();
+        assert_eq!(features, ["default", "nix", "rand", "serde-json"]);
+    }
+
+    #[test]
+    fn test_feature_values() {
+        let features = ["serde-json", "rand", "a-b_c", "a_b-c", "foo_bar", "foo-bar"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect::<Vec<_>>();
+
+        let values = feature_values(&modules(&["serde_json", "rand", "foo_bar"]), &features);
+        assert_eq!(values.unwrap(), ["serde-json", "rand", "foo_bar"]);
+
+        let err = feature_values(&modules(&["rnd"]), &features).unwrap_err();
+        assert!(err.to_string().contains("did you mean `rand`?"));
+        let err = feature_values(&modules(&["a_b_c"]), &features).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("matches the features `a-b_c`, `a_b-c`"));
+    }
+
+    #[test]
+    fn test_declare_alias() {
+        let alias = syn::parse_str::<crate::parse::ArgAlias>("pub as imp").unwrap();
+        let variants = ["a", "b"]
+            .iter()
+            .map(|name| {
+                let ident = syn::Ident::new(name, proc_macro2::Span::call_site());
+                (quote!(feature = #name), ident, name.to_string())
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            declare_alias(Some(&alias), &variants).to_string(),
+            quote! {
+                #[cfg(all(feature = "a", not(any())))]
+                pub use self::a as imp;
+                #[cfg(all(feature = "a", not(any())))]
+                #[doc = "The name of the module selected as `imp`"]
+                #[allow(dead_code)]
+                pub const SELECTED: &str = "a";
+                #[cfg(all(feature = "b", not(any(feature = "a"))))]
+                pub use self::b as imp;
+                #[cfg(all(feature = "b", not(any(feature = "a"))))]
+                #[doc = "The name of the module selected as `imp`"]
+                #[allow(dead_code)]
+                pub const SELECTED: &str = "b";
+            }
+            .to_string()
+        );
+        assert!(declare_alias(None, &variants).is_empty());
+    }
+
+    #[test]
+    fn test_path_attr() {
+        let base = Path::new("/ws/src");
+        let path = Path::new("/ws/src/sys/foo.unix.rs");
+        assert_eq!(path_attr(path, Some(base)).unwrap(), "sys/foo.unix.rs");
+        assert_eq!(path_attr(base, Some(base)).unwrap(), ".");
+        assert_eq!(path_attr(path, None).unwrap(), "/ws/src/sys/foo.unix.rs");
+    }
+
+    #[test]
+    fn test_workspace_root() {
+        let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let root = manifest_dir.parent().unwrap();
+        assert_eq!(workspace_root(manifest_dir), root);
+        assert_eq!(workspace_root(root), root);
+    }
+
+    #[test]
+    fn test_relocate() {
+        let lib = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/lib.rs");
+        let mut ctx = Context::for_file(&lib).unwrap();
+
+        let arg = syn::parse_str(r#"in "../../..""#).unwrap();
+        let err = ctx.relocate(&arg).unwrap_err();
+        assert!(err.to_string().contains("is outside"));
+
+        let arg = syn::parse_str(r#"in "parse""#).unwrap();
+        ctx.relocate(&arg).unwrap();
+        assert!(ctx.dir.unwrap().ends_with("src/parse"));
+        assert!(ctx.explicit_paths);
+    }
+
+    #[test]
+    fn test_collect_paths_collision() {
+        let rename = Rename::Sanitize("_".to_string());
+        let mut paths = Vec::new();
+        collect_paths(&modules(&["a", "b-c"]), "", &rename, &mut paths).unwrap();
+        assert_eq!(paths, ["a", "b_c"]);
+
+        let modules = modules(&["my-module", "my_module"]);
+        let err = collect_paths(&modules, "", &rename, &mut Vec::new()).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("my-module.rs and my_module.rs are both declared as the module `my_module`"));
+    }
+}
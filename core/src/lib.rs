@@ -13,29 +13,18 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use syn::parse::{Parse, ParseStream};
-use syn::punctuated::Punctuated;
-use syn::token;
-use syn::Result;
+//! Module discovery and code generation shared by `dirmod-codegen` and `dirmod-build`.
+//!
+//! This crate is an implementation detail of `dirmod`.
+//! Use the `dirmod` or `dirmod-build` crates instead.
 
-use super::ArgCfg;
-
-#[derive(Clone, Debug)]
-pub struct Args(pub Punctuated<Arg, token::Semi>);
-
-impl Parse for Args {
-    fn parse(input: ParseStream) -> Result<Self> {
-        Ok(Args(Punctuated::parse_terminated(input)?))
-    }
+fn context(ctx: &'static str) -> impl Fn(syn::Error) -> syn::Error {
+    move |err| syn::Error::new(err.span(), format!("Error during {}: {}", ctx, err))
 }
 
-#[derive(Clone, Debug)]
-pub enum Arg {
-    Cfg(ArgCfg),
-}
+mod imp;
+pub use imp::{all, cfg_flag, cfg_key, family, feature, os, out_dir, Context};
 
-impl Parse for Arg {
-    fn parse(input: ParseStream) -> Result<Self> {
-        Ok(Arg::Cfg(input.parse()?))
-    }
-}
+mod parse;
+
+mod targets;
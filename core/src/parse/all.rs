@@ -18,7 +18,10 @@ use syn::punctuated::Punctuated;
 use syn::token;
 use syn::Result;
 
-use super::{kw, ArgDefaultVis, ArgExcept, ArgSpecialVis};
+use super::{
+    kw, parse_list, ArgDefaultVis, ArgExcept, ArgIn, ArgLints, ArgModuleCfg, ArgOnly, ArgOrder,
+    ArgPrefer, ArgRecursive, ArgRename, ArgSpecialVis, ArgTests,
+};
 
 #[derive(Clone, Debug)]
 pub struct Args(pub Punctuated<Arg, token::Semi>);
@@ -34,6 +37,15 @@ pub enum Arg {
     DefaultVis(ArgDefaultVis),
     SpecialVis(ArgSpecialVis),
     Except(ArgExcept),
+    Only(ArgOnly),
+    Cfg(ArgModuleCfg),
+    Tests(ArgTests),
+    Prefer(ArgPrefer),
+    In(ArgIn),
+    Lints(ArgLints),
+    Recursive(ArgRecursive),
+    Rename(ArgRename),
+    Order(ArgOrder),
 }
 
 impl Parse for Arg {
@@ -44,18 +56,43 @@ impl Parse for Arg {
                 module_type: input.parse()?,
                 modifier: input.parse()?,
             })
-        } else if input.peek(token::Priv) || input.peek(token::Pub) {
+        } else if input.peek(token::Priv) || input.peek(token::Pub) || input.peek(token::Pound) {
             Arg::SpecialVis(ArgSpecialVis {
                 modifier: input.parse()?,
-                idents: Punctuated::parse_terminated(input)?,
+                names: parse_list(input)?,
             })
         } else if input.peek(kw::except) {
             Arg::Except(ArgExcept {
                 except: input.parse()?,
-                idents: Punctuated::parse_terminated(input)?,
+                names: parse_list(input)?,
             })
+        } else if input.peek(kw::only) {
+            Arg::Only(ArgOnly {
+                only: input.parse()?,
+                names: parse_list(input)?,
+            })
+        } else if input.peek(kw::cfg) {
+            Arg::Cfg(input.parse()?)
+        } else if input.peek(kw::tests) {
+            Arg::Tests(input.parse()?)
+        } else if input.peek(kw::prefer) {
+            Arg::Prefer(input.parse()?)
+        } else if input.peek(token::In) {
+            Arg::In(input.parse()?)
+        } else if input.peek(kw::lints) {
+            Arg::Lints(ArgLints {
+                lints: input.parse()?,
+            })
+        } else if input.peek(kw::recursive) {
+            Arg::Recursive(ArgRecursive {
+                recursive: input.parse()?,
+            })
+        } else if input.peek(kw::rename) {
+            Arg::Rename(input.parse()?)
+        } else if input.peek(kw::order) {
+            Arg::Order(input.parse()?)
         } else {
-            return Err(input.error("invalid argument for all!()"))
+            return Err(input.error("invalid argument for all!()"));
         };
         Ok(ret)
     }
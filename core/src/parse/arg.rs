@@ -0,0 +1,911 @@
+// dirmod
+// Copyright (C) SOFe
+//
+// Licensed under the Apache License, Version 2.0 (the License);
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an AS IS BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fmt;
+
+use proc_macro2::{Ident, Span, TokenStream};
+use quote::ToTokens;
+use syn::ext::IdentExt;
+use syn::parse::{Parse, ParseStream, Result};
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
+use syn::token;
+
+use super::{kw, Modifier, ModuleTypeKw, PrivVis};
+
+#[derive(Clone, Debug)]
+pub struct ArgDefaultVis {
+    pub(super) default: kw::default,
+    pub module_type: ModuleTypeKw,
+    pub modifier: Modifier,
+}
+
+impl Spanned for ArgDefaultVis {
+    fn span(&self) -> Span {
+        self.default.span()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ArgSpecialVis {
+    pub modifier: Modifier,
+    pub names: Punctuated<ModulePattern, token::Comma>,
+}
+
+impl Spanned for ArgSpecialVis {
+    fn span(&self) -> Span {
+        self.modifier.span()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ArgExcept {
+    pub(super) except: kw::except,
+    pub names: Punctuated<ModulePattern, token::Comma>,
+}
+
+impl Spanned for ArgExcept {
+    fn span(&self) -> Span {
+        self.except.span()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ArgOnly {
+    pub(super) only: kw::only,
+    pub names: Punctuated<ModulePattern, token::Comma>,
+}
+
+impl Spanned for ArgOnly {
+    fn span(&self) -> Span {
+        self.only.span()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ArgModuleCfg {
+    pub(super) cfg: kw::cfg,
+    pub meta: syn::NestedMeta,
+    pub names: Punctuated<ModulePattern, token::Comma>,
+}
+
+impl Parse for ArgModuleCfg {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let cfg = input.parse()?;
+        let content;
+        syn::parenthesized!(content in input);
+        let meta = content.parse()?;
+        if !content.is_empty() {
+            return Err(content.error("expected a single cfg predicate"));
+        }
+        Ok(Self {
+            cfg,
+            meta,
+            names: super::parse_list(input)?,
+        })
+    }
+}
+
+impl Spanned for ArgModuleCfg {
+    fn span(&self) -> Span {
+        self.cfg.span()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ArgPrefer {
+    pub(super) prefer: kw::prefer,
+    pub module_type: ModuleTypeKw,
+    pub names: Punctuated<ModulePath, token::Comma>,
+}
+
+impl Parse for ArgPrefer {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let prefer = input.parse()?;
+        let module_type = input.parse()?;
+        if let ModuleTypeKw::All = module_type {
+            return Err(input.error("expected `file` or `dir`"));
+        }
+        Ok(Self {
+            prefer,
+            module_type,
+            names: super::parse_list(input)?,
+        })
+    }
+}
+
+impl Spanned for ArgPrefer {
+    fn span(&self) -> Span {
+        self.prefer.span()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ArgIn {
+    pub(super) in_token: token::In,
+    pub base: InBase,
+    pub path: syn::LitStr,
+}
+
+impl Parse for ArgIn {
+    fn parse(input: ParseStream) -> Result<Self> {
+        Ok(Self {
+            in_token: input.parse()?,
+            base: input.parse()?,
+            path: input.parse()?,
+        })
+    }
+}
+
+/// The directory that an `in` path is relative to
+#[derive(Clone, Debug)]
+#[allow(dead_code)] // the keyword tokens are only kept for parsing
+pub enum InBase {
+    /// The directory of the calling file
+    File,
+    /// `CARGO_MANIFEST_DIR`
+    Crate(token::Crate),
+    /// `OUT_DIR`
+    OutDir(kw::out_dir),
+}
+
+impl Parse for InBase {
+    fn parse(input: ParseStream) -> Result<Self> {
+        if input.peek(token::Crate) {
+            Ok(Self::Crate(input.parse()?))
+        } else if input.peek(kw::out_dir) {
+            Ok(Self::OutDir(input.parse()?))
+        } else {
+            Ok(Self::File)
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ArgTests {
+    pub(super) tests: kw::tests,
+    /// The test-only modules, or empty if no modules are test-only
+    pub names: Punctuated<ModulePattern, token::Comma>,
+}
+
+impl Parse for ArgTests {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let tests = input.parse()?;
+        let names = if input.is_empty() || input.peek(token::Semi) {
+            Punctuated::new()
+        } else {
+            super::parse_list(input)?
+        };
+        Ok(Self { tests, names })
+    }
+}
+
+impl Spanned for ArgTests {
+    fn span(&self) -> Span {
+        self.tests.span()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ArgLints {
+    pub(super) lints: kw::lints,
+}
+
+impl Spanned for ArgLints {
+    fn span(&self) -> Span {
+        self.lints.span()
+    }
+}
+
+impl Spanned for ArgIn {
+    fn span(&self) -> Span {
+        self.in_token.span
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ArgRecursive {
+    pub(super) recursive: kw::recursive,
+}
+
+impl Spanned for ArgRecursive {
+    fn span(&self) -> Span {
+        self.recursive.span()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ArgRename {
+    pub(super) rename: kw::rename,
+    pub rule: RenameRule,
+}
+
+impl Parse for ArgRename {
+    fn parse(input: ParseStream) -> Result<Self> {
+        Ok(Self {
+            rename: input.parse()?,
+            rule: input.parse()?,
+        })
+    }
+}
+
+impl Spanned for ArgRename {
+    fn span(&self) -> Span {
+        self.rename.span()
+    }
+}
+
+/// The handling of file names that are not valid identifiers
+#[derive(Clone, Debug)]
+#[allow(dead_code)] // the keyword tokens are only kept for parsing
+pub enum RenameRule {
+    /// Report an error for invalid names
+    Error(kw::error),
+    /// Sanitize invalid names, with an optional prefix for names starting with digits
+    Sanitize(kw::sanitize, Option<syn::LitStr>),
+}
+
+impl Parse for RenameRule {
+    fn parse(input: ParseStream) -> Result<Self> {
+        if input.peek(kw::error) {
+            Ok(Self::Error(input.parse()?))
+        } else if input.peek(kw::sanitize) {
+            let sanitize = input.parse()?;
+            let prefix = if input.peek(syn::LitStr) {
+                Some(input.parse()?)
+            } else {
+                None
+            };
+            Ok(Self::Sanitize(sanitize, prefix))
+        } else {
+            Err(input.error("expected `error` or `sanitize`"))
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ArgOrder {
+    pub(super) order: kw::order,
+    pub rule: OrderRule,
+}
+
+impl Parse for ArgOrder {
+    fn parse(input: ParseStream) -> Result<Self> {
+        Ok(Self {
+            order: input.parse()?,
+            rule: input.parse()?,
+        })
+    }
+}
+
+impl Spanned for ArgOrder {
+    fn span(&self) -> Span {
+        self.order.span()
+    }
+}
+
+/// The order of generated module declarations
+#[derive(Clone, Debug)]
+#[allow(dead_code)] // the keyword tokens are only kept for parsing
+pub enum OrderRule {
+    /// Sort by module name
+    Alphabetical(kw::alphabetical),
+    /// Sort by module name, comparing digit sequences numerically
+    Natural(kw::natural),
+    /// List the modules in the given order, followed by the unlisted modules
+    Explicit(Punctuated<ModulePath, token::Comma>),
+}
+
+impl Parse for OrderRule {
+    fn parse(input: ParseStream) -> Result<Self> {
+        // a single module called `natural` or `alphabetical` must be written as `natural,`
+        let single = |input: ParseStream| {
+            input.parse::<Ident>()?;
+            Ok(input.is_empty() || input.peek(token::Semi))
+        };
+        if input.peek(kw::alphabetical) && input.fork().call(single).unwrap_or(false) {
+            Ok(Self::Alphabetical(input.parse()?))
+        } else if input.peek(kw::natural) && input.fork().call(single).unwrap_or(false) {
+            Ok(Self::Natural(input.parse()?))
+        } else {
+            Ok(Self::Explicit(super::parse_list(input)?))
+        }
+    }
+}
+
+/// A module name, or a `::`-separated path to a nested module in recursive mode
+#[derive(Clone, Debug)]
+pub struct ModulePath(pub Punctuated<Ident, token::Colon2>);
+
+impl ModulePath {
+    pub fn is_nested(&self) -> bool {
+        self.0.len() > 1
+    }
+}
+
+impl Parse for ModulePath {
+    fn parse(input: ParseStream) -> Result<Self> {
+        Ok(Self(Punctuated::parse_separated_nonempty(input)?))
+    }
+}
+
+impl ToTokens for ModulePath {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        self.0.to_tokens(tokens)
+    }
+}
+
+impl fmt::Display for ModulePath {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, ident) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, "::")?;
+            }
+            write!(f, "{}", ident.unraw())?;
+        }
+        Ok(())
+    }
+}
+
+/// A module path, a glob pattern like `"*_test"` or a regex pattern like `regex "^v[0-9]+$"`
+#[derive(Clone, Debug)]
+pub enum ModulePattern {
+    Path(ModulePath),
+    Glob(syn::LitStr),
+    Regex(kw::regex, syn::LitStr),
+}
+
+impl ModulePattern {
+    pub fn is_nested(&self) -> bool {
+        match self {
+            Self::Path(path) => path.is_nested(),
+            Self::Glob(glob) => glob.value().contains("::"),
+            Self::Regex(..) => false,
+        }
+    }
+}
+
+impl Parse for ModulePattern {
+    fn parse(input: ParseStream) -> Result<Self> {
+        if input.peek(syn::LitStr) {
+            Ok(Self::Glob(input.parse()?))
+        } else if input.peek(kw::regex) && input.peek2(syn::LitStr) {
+            Ok(Self::Regex(input.parse()?, input.parse()?))
+        } else {
+            Ok(Self::Path(input.parse()?))
+        }
+    }
+}
+
+impl Spanned for ModulePattern {
+    fn span(&self) -> Span {
+        match self {
+            Self::Path(path) => path.span(),
+            Self::Glob(glob) => glob.span(),
+            Self::Regex(regex, _) => regex.span(),
+        }
+    }
+}
+
+impl fmt::Display for ModulePattern {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Path(path) => write!(f, "{}", path),
+            Self::Glob(glob) => write!(f, "{:?}", glob.value()),
+            Self::Regex(_, regex) => write!(f, "regex {:?}", regex.value()),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ArgCustom {
+    pub(super) custom: kw::custom,
+    pub names: Punctuated<Ident, token::Comma>,
+}
+
+impl Parse for ArgCustom {
+    fn parse(input: ParseStream) -> Result<Self> {
+        Ok(Self {
+            custom: input.parse()?,
+            names: super::parse_list(input)?,
+        })
+    }
+}
+
+impl Spanned for ArgCustom {
+    fn span(&self) -> Span {
+        self.custom.span()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ArgExclusive {
+    pub(super) exclusive: kw::exclusive,
+}
+
+impl Spanned for ArgExclusive {
+    fn span(&self) -> Span {
+        self.exclusive.span()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ArgAlias {
+    pub vis: PrivVis,
+    pub(super) as_token: token::As,
+    pub alias: Ident,
+}
+
+impl Parse for ArgAlias {
+    fn parse(input: ParseStream) -> Result<Self> {
+        Ok(Self {
+            vis: input.parse()?,
+            as_token: input.parse()?,
+            alias: input.parse()?,
+        })
+    }
+}
+
+impl Spanned for ArgAlias {
+    fn span(&self) -> Span {
+        self.as_token.span
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ArgCfg {
+    pub modifier: Modifier,
+    pub fallback: Option<(token::OrOr, Fallback)>,
+}
+
+impl Parse for ArgCfg {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let modifier = input.parse()?;
+        let fallback = if input.peek(token::OrOr) {
+            Some((input.parse()?, input.parse()?))
+        } else {
+            None
+        };
+
+        Ok(Self { modifier, fallback })
+    }
+}
+
+/// The behaviour after `||` when none of the cfg modules are compiled
+#[derive(Clone, Debug)]
+pub enum Fallback {
+    /// Trigger `compile_error!` with an optional custom message
+    Error(Option<syn::LitStr>),
+    /// Compile the named module instead
+    Module(Ident),
+}
+
+impl Parse for Fallback {
+    fn parse(input: ParseStream) -> Result<Self> {
+        if input.peek(Ident::peek_any) {
+            Ok(Self::Module(input.call(Ident::parse_any)?))
+        } else {
+            Ok(Self::Error(input.parse()?))
+        }
+    }
+}
+
+impl Spanned for ArgCfg {
+    fn span(&self) -> Span {
+        self.modifier.span()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use matches::assert_matches;
+    use quote::{quote, ToTokens};
+
+    use crate::parse::*;
+
+    #[test]
+    fn test_arg_default_vis() {
+        let arg: all::Arg = syn::parse2(quote!(default pub(crate))).unwrap();
+        let dv = if let all::Arg::DefaultVis(dv) = arg {
+            dv
+        } else {
+            panic!("assertion failed: arg matches Arg::DefaultVis");
+        };
+        assert_matches!(
+            dv.modifier.vis,
+            PrivVis::Vis(syn::Visibility::Restricted(_))
+        );
+        assert!(dv.modifier.imports.is_none());
+    }
+
+    #[test]
+    fn test_arg_default_vis_with_file() {
+        let arg: all::Arg = syn::parse2(quote!(default file pub(crate))).unwrap();
+        let dv = if let all::Arg::DefaultVis(dv) = arg {
+            dv
+        } else {
+            panic!("assertion failed: arg matches Arg::DefaultVis");
+        };
+        assert_matches!(dv.module_type, ModuleTypeKw::File(_));
+        assert_matches!(
+            dv.modifier.vis,
+            PrivVis::Vis(syn::Visibility::Restricted(_))
+        );
+        assert!(dv.modifier.imports.is_none());
+    }
+
+    #[test]
+    fn test_arg_default_vis_with_dir() {
+        let arg: all::Arg = syn::parse2(quote!(default dir pub(crate))).unwrap();
+        let dv = if let all::Arg::DefaultVis(dv) = arg {
+            dv
+        } else {
+            panic!("assertion failed: arg matches Arg::DefaultVis");
+        };
+        assert_matches!(dv.module_type, ModuleTypeKw::Dir(_));
+        assert_matches!(
+            dv.modifier.vis,
+            PrivVis::Vis(syn::Visibility::Restricted(_))
+        );
+        assert!(dv.modifier.imports.is_none());
+    }
+
+    #[test]
+    fn test_arg_special_vis() {
+        let arg: all::Arg = syn::parse2(quote!(pub use foo)).unwrap();
+        let sv = if let all::Arg::SpecialVis(sv) = arg {
+            sv
+        } else {
+            panic!("assertion failed: arg matches Arg::SpecialVis(_)")
+        };
+        assert_matches!(sv.modifier.vis, PrivVis::Vis(syn::Visibility::Public(_)));
+        assert!(sv.modifier.imports.is_some());
+        assert_eq!(
+            sv.names
+                .into_iter()
+                .map(|s| s.to_string())
+                .collect::<Vec<_>>(),
+            vec!["foo".to_string()]
+        );
+
+        let arg: all::Arg = syn::parse2(quote!(priv foo, bar)).unwrap();
+        let sv = if let all::Arg::SpecialVis(sv) = arg {
+            sv
+        } else {
+            panic!("assertion failed: arg matches Arg::SpecialVis(_)")
+        };
+        assert_matches!(sv.modifier.vis, PrivVis::Priv(_));
+        assert!(sv.modifier.imports.is_none());
+        assert_eq!(
+            sv.names
+                .into_iter()
+                .map(|s| s.to_string())
+                .collect::<Vec<_>>(),
+            vec!["foo".to_string(), "bar".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_arg_except() {
+        let arg: all::Arg = syn::parse2(quote!(except corge, grault)).unwrap();
+        let ex = if let all::Arg::Except(ex) = arg {
+            ex
+        } else {
+            panic!("assertion failed: arg matches Arg::Except(_)")
+        };
+
+        assert_eq!(
+            ex.names
+                .into_iter()
+                .map(|s| s.to_string())
+                .collect::<Vec<_>>(),
+            vec!["corge", "grault"]
+        );
+    }
+
+    #[test]
+    fn test_arg_except_nested() {
+        let arg: all::Arg = syn::parse2(quote!(except foo::bar, corge)).unwrap();
+        let ex = if let all::Arg::Except(ex) = arg {
+            ex
+        } else {
+            panic!("assertion failed: arg matches Arg::Except(_)")
+        };
+
+        let names = ex.names.into_iter().collect::<Vec<_>>();
+        assert!(names[0].is_nested());
+        assert!(!names[1].is_nested());
+        assert_eq!(
+            names.iter().map(|s| s.to_string()).collect::<Vec<_>>(),
+            vec!["foo::bar", "corge"]
+        );
+    }
+
+    #[test]
+    fn test_arg_patterns() {
+        let arg: all::Arg = syn::parse2(quote!(except "*_test", regex "^v[0-9]+$", foo)).unwrap();
+        let ex = if let all::Arg::Except(ex) = arg {
+            ex
+        } else {
+            panic!("assertion failed: arg matches Arg::Except(_)")
+        };
+        let names = ex.names.into_iter().collect::<Vec<_>>();
+        assert_matches!(&names[0], ModulePattern::Glob(_));
+        assert_matches!(&names[1], ModulePattern::Regex(..));
+        assert_matches!(&names[2], ModulePattern::Path(_));
+
+        let arg: all::Arg = syn::parse2(quote!(pub(crate) "internal_*")).unwrap();
+        assert_matches!(
+            arg,
+            all::Arg::SpecialVis(sv) if matches!(sv.names[0], ModulePattern::Glob(_))
+        );
+
+        let args: all::Args = syn::parse2(quote!(only "handler_*", regex; pub regex)).unwrap();
+        let only = if let all::Arg::Only(only) = &args.0[0] {
+            only
+        } else {
+            panic!("assertion failed: arg matches Arg::Only(_)")
+        };
+        assert_eq!(
+            only.names.iter().map(|s| s.to_string()).collect::<Vec<_>>(),
+            vec!["\"handler_*\"".to_string(), "regex".to_string()]
+        );
+        assert_matches!(&args.0[1], all::Arg::SpecialVis(_));
+    }
+
+    #[test]
+    fn test_args_multiple_lists() {
+        let args: all::Args =
+            syn::parse2(quote!(pub foo, bar; except qux, corge,; priv grault)).unwrap();
+        assert_eq!(args.0.len(), 3);
+        assert_matches!(&args.0[0], all::Arg::SpecialVis(sv) if sv.names.len() == 2);
+        assert_matches!(&args.0[1], all::Arg::Except(ex) if ex.names.len() == 2);
+        assert_matches!(&args.0[2], all::Arg::SpecialVis(sv) if sv.names.len() == 1);
+    }
+
+    #[test]
+    fn test_arg_rename() {
+        let arg: all::Arg = syn::parse2(quote!(rename sanitize)).unwrap();
+        assert_matches!(
+            arg,
+            all::Arg::Rename(ArgRename {
+                rule: RenameRule::Sanitize(_, None),
+                ..
+            })
+        );
+
+        let arg: all::Arg = syn::parse2(quote!(rename sanitize "m")).unwrap();
+        assert_matches!(
+            arg,
+            all::Arg::Rename(ArgRename {
+                rule: RenameRule::Sanitize(_, Some(_)),
+                ..
+            })
+        );
+
+        let arg: cfg::Arg = syn::parse2(quote!(rename error)).unwrap();
+        assert_matches!(
+            arg,
+            cfg::Arg::Rename(ArgRename {
+                rule: RenameRule::Error(_),
+                ..
+            })
+        );
+    }
+
+    #[test]
+    fn test_arg_order() {
+        let arg: all::Arg = syn::parse2(quote!(order natural)).unwrap();
+        assert_matches!(
+            arg,
+            all::Arg::Order(ArgOrder {
+                rule: OrderRule::Natural(_),
+                ..
+            })
+        );
+
+        let args: all::Args = syn::parse2(quote!(order alphabetical; default pub)).unwrap();
+        assert_matches!(
+            args.0[0],
+            all::Arg::Order(ArgOrder {
+                rule: OrderRule::Alphabetical(_),
+                ..
+            })
+        );
+
+        let arg: all::Arg = syn::parse2(quote!(order zero, natural, a::b)).unwrap();
+        let names = if let all::Arg::Order(ArgOrder {
+            rule: OrderRule::Explicit(names),
+            ..
+        }) = arg
+        {
+            names
+        } else {
+            panic!("assertion failed: arg matches Arg::Order(OrderRule::Explicit(_))")
+        };
+        assert_eq!(
+            names.into_iter().map(|s| s.to_string()).collect::<Vec<_>>(),
+            vec![
+                "zero".to_string(),
+                "natural".to_string(),
+                "a::b".to_string()
+            ]
+        );
+    }
+
+    #[test]
+    fn test_arg_prefer() {
+        let arg: all::Arg = syn::parse2(quote!(prefer dir foo, bar)).unwrap();
+        let prefer = if let all::Arg::Prefer(prefer) = arg {
+            prefer
+        } else {
+            panic!("assertion failed: arg matches Arg::Prefer(_)")
+        };
+        assert_matches!(prefer.module_type, ModuleTypeKw::Dir(_));
+        assert_eq!(prefer.names.len(), 2);
+
+        let arg: all::Arg = syn::parse2(quote!(prefer file foo)).unwrap();
+        assert_matches!(
+            arg,
+            all::Arg::Prefer(ArgPrefer {
+                module_type: ModuleTypeKw::File(_),
+                ..
+            })
+        );
+
+        assert!(syn::parse2::<all::Arg>(quote!(prefer foo)).is_err());
+    }
+
+    #[test]
+    fn test_arg_module_cfg() {
+        let arg: all::Arg = syn::parse2(quote!(cfg(feature = "serde") serde_impl, json)).unwrap();
+        let cfg = if let all::Arg::Cfg(cfg) = arg {
+            cfg
+        } else {
+            panic!("assertion failed: arg matches Arg::Cfg(_)")
+        };
+        assert_token_eq(cfg.meta.to_token_stream(), quote!(feature = "serde"));
+        assert_eq!(cfg.names.len(), 2);
+
+        let arg: all::Arg = syn::parse2(quote!(cfg(any(unix, windows)) "sys_*")).unwrap();
+        assert_matches!(arg, all::Arg::Cfg(_));
+
+        assert!(syn::parse2::<all::Arg>(quote!(cfg(unix, windows) sys)).is_err());
+        assert!(syn::parse2::<all::Arg>(quote!(cfg(unix))).is_err());
+    }
+
+    #[test]
+    fn test_arg_tests() {
+        let arg: all::Arg = syn::parse2(quote!(tests "*_spec", fixtures)).unwrap();
+        let tests = if let all::Arg::Tests(tests) = arg {
+            tests
+        } else {
+            panic!("assertion failed: arg matches Arg::Tests(_)")
+        };
+        assert_eq!(tests.names.len(), 2);
+
+        let args: all::Args = syn::parse2(quote!(tests; default pub)).unwrap();
+        assert_matches!(&args.0[0], all::Arg::Tests(tests) if tests.names.is_empty());
+        assert_matches!(args.0[1], all::Arg::DefaultVis(_));
+    }
+
+    #[test]
+    fn test_arg_in() {
+        let arg: all::Arg = syn::parse2(quote!(in "../shared")).unwrap();
+        assert_matches!(
+            arg,
+            all::Arg::In(ArgIn {
+                base: InBase::File,
+                ..
+            })
+        );
+
+        let args: cfg::Args = syn::parse2(quote!(in crate "src/sys"; pub use ||)).unwrap();
+        let arg_in = if let cfg::Arg::In(arg_in) = &args.0[0] {
+            arg_in
+        } else {
+            panic!("assertion failed: arg matches Arg::In(_)")
+        };
+        assert_matches!(arg_in.base, InBase::Crate(_));
+        assert_eq!(arg_in.path.value(), "src/sys");
+        assert_matches!(args.0[1], cfg::Arg::Cfg(_));
+    }
+
+    #[test]
+    fn test_arg_custom() {
+        let args: cfg::Args = syn::parse2(quote!(pub use ||; custom myos, other_os)).unwrap();
+        assert_matches!(args.0[0], cfg::Arg::Cfg(_));
+        let custom = if let cfg::Arg::Custom(custom) = &args.0[1] {
+            custom
+        } else {
+            panic!("assertion failed: arg matches Arg::Custom(_)")
+        };
+        assert_eq!(custom.names.len(), 2);
+
+        assert!(syn::parse2::<cfg::Arg>(quote!(custom)).is_err());
+    }
+
+    #[test]
+    fn test_arg_exclusive() {
+        let args: cfg::Args = syn::parse2(quote!(pub use; exclusive)).unwrap();
+        assert_matches!(args.0[0], cfg::Arg::Cfg(_));
+        assert_matches!(args.0[1], cfg::Arg::Exclusive(_));
+    }
+
+    #[test]
+    fn test_arg_alias() {
+        let arg: cfg::Arg = syn::parse2(quote!(as imp)).unwrap();
+        let alias = if let cfg::Arg::Alias(alias) = arg {
+            alias
+        } else {
+            panic!("assertion failed: arg matches Arg::Alias(_)")
+        };
+        assert_matches!(alias.vis, PrivVis::Vis(syn::Visibility::Inherited));
+        assert_eq!(alias.alias.to_string(), "imp");
+
+        let args: cfg::Args = syn::parse2(quote!(pub(crate) ||; pub(crate) as sys)).unwrap();
+        assert_matches!(args.0[0], cfg::Arg::Cfg(_));
+        assert_matches!(
+            &args.0[1],
+            cfg::Arg::Alias(ArgAlias {
+                vis: PrivVis::Vis(syn::Visibility::Restricted(_)),
+                ..
+            })
+        );
+
+        assert!(syn::parse2::<cfg::Arg>(quote!(pub as)).is_err());
+    }
+
+    #[test]
+    fn test_arg_cfg() {
+        let arg: cfg::Arg = syn::parse2(quote!(pub use)).unwrap();
+        assert_matches!(arg, cfg::Arg::Cfg(ArgCfg { fallback: None, .. }));
+
+        let arg: cfg::Arg = syn::parse2(quote!(pub use ||)).unwrap();
+        assert_matches!(
+            arg,
+            cfg::Arg::Cfg(ArgCfg {
+                fallback: Some((_, Fallback::Error(None))),
+                ..
+            })
+        );
+
+        let arg: cfg::Arg = syn::parse2(quote!(pub || "unsupported")).unwrap();
+        assert_matches!(
+            arg,
+            cfg::Arg::Cfg(ArgCfg {
+                fallback: Some((_, Fallback::Error(Some(_)))),
+                ..
+            })
+        );
+
+        let args: cfg::Args = syn::parse2(quote!(pub use || generic; rename error)).unwrap();
+        assert_matches!(
+            &args.0[0],
+            cfg::Arg::Cfg(ArgCfg {
+                fallback: Some((_, Fallback::Module(ident))),
+                ..
+            }) if ident == "generic"
+        );
+        assert_matches!(args.0[1], cfg::Arg::Rename(_));
+    }
+
+    #[test]
+    fn test_arg_recursive() {
+        let args: all::Args = syn::parse2(quote!(recursive; default pub)).unwrap();
+        assert_matches!(args.0[0], all::Arg::Recursive(_));
+        assert_matches!(args.0[1], all::Arg::DefaultVis(_));
+    }
+}
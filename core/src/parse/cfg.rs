@@ -0,0 +1,154 @@
+// dirmod
+// Copyright (C) SOFe
+//
+// Licensed under the Apache License, Version 2.0 (the License);
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an AS IS BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::token;
+use syn::Result;
+
+use super::{
+    kw, parse_list, ArgAlias, ArgCfg, ArgCustom, ArgDefaultVis, ArgExcept, ArgExclusive, ArgIn,
+    ArgOrder, ArgRename, ArgSpecialVis, Modifier, PrivVis,
+};
+
+#[derive(Clone, Debug)]
+pub struct Args(pub Punctuated<Arg, token::Semi>);
+
+impl Parse for Args {
+    fn parse(input: ParseStream) -> Result<Self> {
+        Ok(Args(Punctuated::parse_terminated(input)?))
+    }
+}
+
+/// The arguments of `cfg_key!`, i.e. a cfg key followed by the arguments of `os!`
+#[derive(Clone, Debug)]
+pub struct KeyedArgs {
+    pub key: syn::Ident,
+    pub args: Args,
+}
+
+impl Parse for KeyedArgs {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let key = input.parse()?;
+        if !input.is_empty() {
+            input.parse::<token::Semi>()?;
+        }
+        Ok(Self {
+            key,
+            args: input.parse()?,
+        })
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum Arg {
+    Cfg(ArgCfg),
+    DefaultVis(ArgDefaultVis),
+    SpecialVis(ArgSpecialVis),
+    Except(ArgExcept),
+    Alias(ArgAlias),
+    Exclusive(ArgExclusive),
+    Rename(ArgRename),
+    In(ArgIn),
+    Order(ArgOrder),
+    Custom(ArgCustom),
+}
+
+impl Parse for Arg {
+    fn parse(input: ParseStream) -> Result<Self> {
+        if input.peek(kw::rename) {
+            Ok(Arg::Rename(input.parse()?))
+        } else if input.peek(token::In) {
+            Ok(Arg::In(input.parse()?))
+        } else if input.peek(kw::order) {
+            Ok(Arg::Order(input.parse()?))
+        } else if input.peek(kw::exclusive) {
+            Ok(Arg::Exclusive(ArgExclusive {
+                exclusive: input.parse()?,
+            }))
+        } else if input.peek(kw::custom) {
+            Ok(Arg::Custom(input.parse()?))
+        } else if input.peek(kw::default) {
+            Ok(Arg::DefaultVis(ArgDefaultVis {
+                default: input.parse()?,
+                module_type: input.parse()?,
+                modifier: input.parse()?,
+            }))
+        } else if input.peek(kw::except) {
+            Ok(Arg::Except(ArgExcept {
+                except: input.parse()?,
+                names: parse_list(input)?,
+            }))
+        } else if peek_alias(input) {
+            Ok(Arg::Alias(input.parse()?))
+        } else {
+            // a modifier followed by module names is a per-module visibility
+            let fork = input.fork();
+            fork.parse::<Modifier>()?;
+            if fork.is_empty() || fork.peek(token::Semi) || fork.peek(token::OrOr) {
+                Ok(Arg::Cfg(input.parse()?))
+            } else {
+                Ok(Arg::SpecialVis(ArgSpecialVis {
+                    modifier: input.parse()?,
+                    names: parse_list(input)?,
+                }))
+            }
+        }
+    }
+}
+
+/// Checks whether the input starts with `[$vis] as`
+fn peek_alias(input: ParseStream) -> bool {
+    let fork = input.fork();
+    fork.parse::<PrivVis>().is_ok() && fork.peek(token::As)
+}
+
+#[cfg(test)]
+mod tests {
+    use matches::assert_matches;
+    use quote::quote;
+
+    use crate::parse::*;
+
+    #[test]
+    fn test_keyed_args() {
+        let args: cfg::KeyedArgs = syn::parse2(quote!(target_arch)).unwrap();
+        assert_eq!(args.key.to_string(), "target_arch");
+        assert!(args.args.0.is_empty());
+
+        let args: cfg::KeyedArgs = syn::parse2(quote!(target_pointer_width; pub use ||)).unwrap();
+        assert_eq!(args.key.to_string(), "target_pointer_width");
+        assert_matches!(&args.args.0[0], cfg::Arg::Cfg(arg) if arg.fallback.is_some());
+
+        assert!(syn::parse2::<cfg::KeyedArgs>(quote!(pub use)).is_err());
+    }
+
+    #[test]
+    fn test_args_with_common_modules() {
+        let args: cfg::Args =
+            syn::parse2(quote!(pub use ||; pub(crate) common, util; except scratch)).unwrap();
+        assert_matches!(&args.0[0], cfg::Arg::Cfg(arg) if arg.fallback.is_some());
+        assert_matches!(&args.0[1], cfg::Arg::SpecialVis(sv) if sv.names.len() == 2);
+        assert_matches!(&args.0[2], cfg::Arg::Except(except) if except.names.len() == 1);
+
+        let args: cfg::Args = syn::parse2(quote!(default pub use; priv "*_util")).unwrap();
+        assert_matches!(&args.0[0], cfg::Arg::DefaultVis(_));
+        assert_matches!(&args.0[1], cfg::Arg::SpecialVis(_));
+
+        let args: cfg::Args = syn::parse2(quote!(pub; #[doc(hidden)] pub common)).unwrap();
+        assert_matches!(&args.0[0], cfg::Arg::Cfg(arg) if arg.fallback.is_none());
+        assert_matches!(&args.0[1], cfg::Arg::SpecialVis(_));
+    }
+}
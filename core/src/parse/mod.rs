@@ -17,6 +17,7 @@
 
 pub mod all;
 pub mod cfg;
+pub mod out_dir;
 
 mod modifier;
 pub use modifier::*;
@@ -35,13 +36,47 @@ mod kw {
     syn::custom_keyword!(except);
     syn::custom_keyword!(file);
     syn::custom_keyword!(dir);
+    syn::custom_keyword!(recursive);
+    syn::custom_keyword!(rename);
+    syn::custom_keyword!(error);
+    syn::custom_keyword!(sanitize);
+    syn::custom_keyword!(order);
+    syn::custom_keyword!(alphabetical);
+    syn::custom_keyword!(natural);
+    syn::custom_keyword!(only);
+    syn::custom_keyword!(regex);
+    syn::custom_keyword!(prefer);
+    syn::custom_keyword!(out_dir);
+    syn::custom_keyword!(lints);
+    syn::custom_keyword!(cfg);
+    syn::custom_keyword!(tests);
+    syn::custom_keyword!(custom);
+    syn::custom_keyword!(exclusive);
+}
+
+/// Parses a non-empty comma-separated list terminated by `;` or the end of input
+fn parse_list<T: syn::parse::Parse>(
+    input: syn::parse::ParseStream,
+) -> syn::Result<syn::punctuated::Punctuated<T, syn::token::Comma>> {
+    let mut list = syn::punctuated::Punctuated::new();
+    loop {
+        list.push_value(input.parse()?);
+        if input.is_empty() || input.peek(syn::token::Semi) {
+            break;
+        }
+        list.push_punct(input.parse()?);
+        if input.is_empty() || input.peek(syn::token::Semi) {
+            break;
+        }
+    }
+    Ok(list)
 }
 
 #[cfg(test)]
 fn assert_token_eq(a: proc_macro2::TokenStream, b: proc_macro2::TokenStream) {
     use proc_macro2::TokenTree;
 
-    for (p, q) in a.clone().into_iter().zip(b.clone().into_iter()) {
+    for (p, q) in a.clone().into_iter().zip(b.clone()) {
         match (p, q) {
             (TokenTree::Punct(p), TokenTree::Punct(q)) => assert_eq!(p.as_char(), q.as_char()),
             (TokenTree::Ident(p), TokenTree::Ident(q)) => assert_eq!(p.to_string(), q.to_string()),
@@ -20,37 +20,47 @@ use syn::token;
 
 use super::PrivVis;
 
-/// A combination of `PrivVis` + an optional `use`
+/// A combination of `PrivVis` + an optional `use`, with outer attributes
 #[derive(Clone, Debug)]
 pub struct Modifier {
+    /// The attributes before the visibility, added to the `mod` item
+    pub mod_attrs: Vec<syn::Attribute>,
     pub vis: PrivVis,
+    /// The attributes before `use`, added to the `use` item
+    pub use_attrs: Vec<syn::Attribute>,
     pub imports: Option<token::Use>,
 }
 
 impl Modifier {
     pub fn default_file() -> Self {
         Self {
+            mod_attrs: Vec::new(),
             vis: PrivVis::Priv(token::Priv {
                 span: Span::call_site(),
             }),
+            use_attrs: Vec::new(),
             imports: Some(token::Use(Span::call_site())),
         }
     }
 
     pub fn default_dir() -> Self {
         Self {
+            mod_attrs: Vec::new(),
             vis: PrivVis::Priv(token::Priv {
                 span: Span::call_site(),
             }),
+            use_attrs: Vec::new(),
             imports: None,
         }
     }
 
     pub fn default_cfg() -> Self {
         Self {
+            mod_attrs: Vec::new(),
             vis: PrivVis::Priv(token::Priv {
                 span: Span::call_site(),
             }),
+            use_attrs: Vec::new(),
             imports: None,
         }
     }
@@ -58,13 +68,21 @@ impl Modifier {
 
 impl Parse for Modifier {
     fn parse(input: ParseStream) -> Result<Self> {
+        let mod_attrs = input.call(syn::Attribute::parse_outer)?;
+        let vis = input.parse()?;
+        let use_attrs = input.call(syn::Attribute::parse_outer)?;
+        let imports = if input.peek(token::Use) {
+            Some(input.parse()?)
+        } else if !use_attrs.is_empty() {
+            return Err(input.error("expected `use` after the attributes"));
+        } else {
+            None
+        };
         Ok(Self {
-            vis: input.parse()?,
-            imports: if input.peek(token::Use) {
-                Some(input.parse()?)
-            } else {
-                None
-            },
+            mod_attrs,
+            vis,
+            use_attrs,
+            imports,
         })
     }
 }
@@ -108,4 +126,27 @@ mod tests {
         assert_matches!(modifier.vis, PrivVis::Vis(syn::Visibility::Restricted(_)));
         assert!(modifier.imports.is_some());
     }
+
+    #[test]
+    fn test_parse_modifier_attrs() {
+        let modifier: Modifier = syn::parse2(quote!(#[doc(hidden)] pub)).unwrap();
+        assert_eq!(modifier.mod_attrs.len(), 1);
+        assert_matches!(modifier.vis, PrivVis::Vis(syn::Visibility::Public(_)));
+        assert!(modifier.use_attrs.is_empty());
+        assert!(modifier.imports.is_none());
+
+        let modifier: Modifier =
+            syn::parse2(quote!(#[allow(dead_code)] #[rustfmt::skip] pub #[doc(hidden)] use))
+                .unwrap();
+        assert_eq!(modifier.mod_attrs.len(), 2);
+        assert_eq!(modifier.use_attrs.len(), 1);
+        assert!(modifier.imports.is_some());
+
+        let modifier: Modifier = syn::parse2(quote!(priv #[doc(hidden)] use)).unwrap();
+        assert_matches!(modifier.vis, PrivVis::Priv(_));
+        assert_eq!(modifier.use_attrs.len(), 1);
+
+        assert!(syn::parse2::<Modifier>(quote!(pub #[doc(hidden)])).is_err());
+        assert!(syn::parse2::<Modifier>(quote!(#![doc(hidden)] pub)).is_err());
+    }
 }
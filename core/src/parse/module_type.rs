@@ -19,6 +19,7 @@ use syn::parse::{Parse, ParseStream, Result};
 use super::kw;
 
 #[derive(Clone, Debug)]
+#[allow(dead_code)] // the keyword tokens are only kept for parsing
 pub enum ModuleTypeKw {
     File(kw::file),
     Dir(kw::dir),
@@ -0,0 +1,84 @@
+// dirmod
+// Copyright (C) SOFe
+//
+// Licensed under the Apache License, Version 2.0 (the License);
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an AS IS BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::token;
+use syn::Result;
+
+use super::{all, kw, ArgDefaultVis, ArgIn, InBase, ModuleTypeKw};
+
+/// The arguments of `out_dir!`, converted to the equivalent arguments of `all!`
+#[derive(Clone, Debug)]
+pub struct Args(pub all::Args);
+
+impl Parse for Args {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let path: syn::LitStr = input.parse()?;
+        let span = path.span();
+        let mut args = Punctuated::new();
+        args.push(all::Arg::In(ArgIn {
+            in_token: token::In(span),
+            base: InBase::OutDir(kw::out_dir(span)),
+            path,
+        }));
+
+        if input.peek(token::Comma) {
+            input.parse::<token::Comma>()?;
+            args.push(all::Arg::DefaultVis(ArgDefaultVis {
+                default: kw::default(span),
+                module_type: ModuleTypeKw::All,
+                modifier: input.parse()?,
+            }));
+        }
+        if !input.is_empty() {
+            input.parse::<token::Semi>()?;
+            args.extend(input.parse::<all::Args>()?.0);
+        }
+        Ok(Self(all::Args(args)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use matches::assert_matches;
+    use quote::quote;
+
+    use crate::parse::*;
+
+    #[test]
+    fn test_out_dir_args() {
+        let args: out_dir::Args = syn::parse2(quote!("protos")).unwrap();
+        assert_eq!(args.0 .0.len(), 1);
+        assert_matches!(&args.0 .0[0], all::Arg::In(arg) if arg.path.value() == "protos");
+
+        let args: out_dir::Args = syn::parse2(quote!("protos", pub use; except foo)).unwrap();
+        assert_eq!(args.0 .0.len(), 3);
+        assert_matches!(
+            &args.0 .0[0],
+            all::Arg::In(ArgIn {
+                base: InBase::OutDir(_),
+                ..
+            })
+        );
+        assert_matches!(&args.0 .0[1], all::Arg::DefaultVis(dv) if dv.modifier.imports.is_some());
+        assert_matches!(&args.0 .0[2], all::Arg::Except(_));
+
+        let args: out_dir::Args = syn::parse2(quote!("", pub; lints)).unwrap();
+        assert_matches!(&args.0 .0[2], all::Arg::Lints(_));
+
+        assert!(syn::parse2::<out_dir::Args>(quote!(pub use)).is_err());
+    }
+}
@@ -19,6 +19,7 @@ use syn::parse::{Parse, ParseStream, Result};
 use syn::token;
 
 #[derive(Clone, Debug)]
+#[allow(dead_code)] // the keyword token is only kept for parsing
 pub enum PrivVis {
     Priv(token::Priv),
     Vis(syn::Visibility),
@@ -0,0 +1,106 @@
+// dirmod
+// Copyright (C) SOFe
+//
+// Licensed under the Apache License, Version 2.0 (the License);
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an AS IS BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Known values of the target cfg keys.
+//!
+//! The lists follow the values expected by `rustc --check-cfg`.
+
+/// The known values of `target_family`,
+/// in the order that their `foo.<family>.rs` variants take precedence,
+/// since a target may belong to several families, e.g. emscripten is both `unix` and `wasm`
+pub(crate) const FAMILIES: &[&str] = &["wasm", "unix", "windows"];
+
+/// The known values of `target_os`
+pub(crate) const OSES: &[&str] = &[
+    "aix",
+    "amdhsa",
+    "android",
+    "cuda",
+    "cygwin",
+    "dragonfly",
+    "emscripten",
+    "espidf",
+    "freebsd",
+    "fuchsia",
+    "haiku",
+    "helenos",
+    "hermit",
+    "horizon",
+    "hurd",
+    "illumos",
+    "ios",
+    "l4re",
+    "linux",
+    "lynxos178",
+    "macos",
+    "managarm",
+    "motor",
+    "netbsd",
+    "none",
+    "nto",
+    "nuttx",
+    "openbsd",
+    "psp",
+    "psx",
+    "qurt",
+    "redox",
+    "rtems",
+    "solaris",
+    "solid_asp3",
+    "teeos",
+    "trusty",
+    "tvos",
+    "uefi",
+    "unknown",
+    "vexos",
+    "visionos",
+    "vita",
+    "vxworks",
+    "wasi",
+    "watchos",
+    "windows",
+    "xous",
+    "zkvm",
+];
+
+/// The `target_os` values recognized as file suffixes like `foo.linux.rs`.
+///
+/// This is a deliberate subset of [`OSES`]:
+/// values that are common words, like `none`, `unknown` or `motor`,
+/// and rarely targeted platforms are left out so that files like `config.none.rs`
+/// remain ordinary modules.
+pub(crate) const SUFFIX_OSES: &[&str] = &[
+    "aix",
+    "android",
+    "cygwin",
+    "dragonfly",
+    "emscripten",
+    "freebsd",
+    "fuchsia",
+    "haiku",
+    "hurd",
+    "illumos",
+    "ios",
+    "linux",
+    "macos",
+    "netbsd",
+    "openbsd",
+    "redox",
+    "solaris",
+    "tvos",
+    "visionos",
+    "wasi",
+    "watchos",
+];
@@ -38,6 +38,45 @@
 //! like `dirmod::all!()`, `dirmod::os!()`, etc. for clarity.
 //! The old `#[macro_use] extern crate dirmod;` style is not recommended.)*
 //!
+//! ## Module layout
+//! `dirmod` supports both the mod.rs layout and the Rust 2018 non-mod.rs layout.
+//! When called from mod.rs, lib.rs, main.rs or another crate root like `src/bin/tool.rs`,
+//! the directory of the calling file is scanned.
+//! When called from any other file like `src/handlers.rs`, the `src/handlers/` directory is
+//! scanned instead.
+//!
+//! A subdirectory is declared as a directory module
+//! if it contains a mod.rs or it has a sibling file with the same name (`foo/` and `foo.rs`).
+//! If both `foo.rs` and `foo/mod.rs` exist, `prefer file foo` or `prefer dir foo` chooses one of them.
+//!
+//! ### Recursive mode
+//! Subdirectories that only group modules together do not need their own mod.rs.
+//! With the `recursive` argument, they are declared as inline modules instead:
+//!
+//! ```ignore
+//! dirmod::all!(recursive; default pub; except handlers::legacy);
+//! ```
+//!
+//! This declares `mod handlers { pub mod login; pub mod logout; }` for the files
+//! `handlers/login.rs` and `handlers/logout.rs`, where `handlers/` has no mod.rs and no sibling
+//! `handlers.rs`.
+//!
+//! ### Module names
+//! Files named after keywords, like `type.rs`, are declared as raw identifiers (`mod r#type;`).
+//! File names that cannot be identifiers, like `my-module.rs` or `001_init.rs`,
+//! are rejected unless `rename sanitize` is passed,
+//! which declares them as `my_module` and `_001_init` with the corresponding `#[path]`.
+//!
+//! ### Module order
+//! Modules are declared in alphabetical order, regardless of the file system.
+//! Since `macro_rules!` macros are only visible to modules declared after them,
+//! the order can be changed with the `order` argument:
+//!
+//! ```ignore
+//! dirmod::all!(order macros, prelude); // `macros` and `prelude` first, the rest alphabetically
+//! dirmod::all!(order natural); // `2_x` before `10_x`
+//! ```
+//!
 //! ## Visibility
 //! ### Default visibility
 //! All modules can be set to a common visibility,
@@ -78,12 +117,35 @@
 //!
 //! Then all modules have `pub` visibility,
 //! except `foo` and `bar` which are private.
+//! Names that do not refer to any module, e.g. after a module is renamed, are reported as errors.
 //!
 //! Similarly, if all modules are publicly re-exported and `foo` and `bar` are only exported as modules:
 //! ```ignore
 //! dirmod::all!(default pub use; pub foo, bar);
 //! ```
 //!
+//! ### Attributes
+//! Attributes written before the visibility are added to the `mod` items,
+//! and attributes written before `use` are added to the re-exports:
+//!
+//! ```ignore
+//! dirmod::all!(default #[allow(dead_code)] pub use; #[doc(hidden)] pub internal);
+//! ```
+//!
+//! ### Name patterns
+//! Modules following a naming convention can be matched by glob patterns in string literals,
+//! or by regex patterns after the `regex` keyword:
+//!
+//! ```ignore
+//! dirmod::all!(default pub; pub(crate) "internal_*"; except "*_test", regex "^scratch[0-9]*$");
+//! ```
+//!
+//! The `only` argument declares the matching modules only:
+//!
+//! ```ignore
+//! dirmod::all!(default pub; only "handler_*");
+//! ```
+//!
 //! ## Conditional compilation
 //! > But I use `mod` to implement conditional compilation!
 //!
@@ -112,8 +174,98 @@
 //! dirmod::os!(pub use || "custom error message");
 //! ```
 //!
+//! Or compile a fallback module like `generic.rs` for the other operating systems instead:
+//!
+//! ```ignore
+//! dirmod::os!(pub use || generic);
+//! ```
+//!
+//! Modules shared by all platforms can sit next to the platform-specific modules.
+//! They are declared unconditionally if they are given their own visibility,
+//! and other modules can be excluded with `except`:
+//!
+//! ```ignore
+//! dirmod::os!(pub use ||; pub(crate) common, util; except scratch);
+//! ```
+//!
+//! If the platform modules define items with the same names,
+//! the selected module can be imported under a common name instead,
+//! together with a `SELECTED` constant naming the module:
+//!
+//! ```ignore
+//! dirmod::os!(||; as imp); // then call `imp::open()`
+//! ```
+//!
+//! Module names of `dirmod::os!()` and `dirmod::family!()` are checked against the known targets,
+//! so a typo like `linix.rs` is reported instead of silently never being compiled.
+//! Names of custom targets can be allowed with the `custom` argument:
+//!
+//! ```ignore
+//! dirmod::os!(pub use ||; custom myos);
+//! ```
+//!
 //! Note that it does not make sense to use the `||` on `dirmod::feature!`,
 //! because Cargo features are incremental and should not be restricted in amount.
+//! The exception is features selecting one of several backends,
+//! where exactly one of the features must be enabled:
+//!
+//! ```ignore
+//! dirmod::feature!(pub use; exclusive); // tokio.rs, async_std.rs, smol.rs
+//! ```
+//!
+//! `dirmod::feature!` reads the features declared in Cargo.toml,
+//! so the module `async_std` is compiled with the feature `async-std`,
+//! and a module without a corresponding feature is reported as an error.
+//!
+//! A feature module `foo` can be accompanied by a stub `foo.disabled.rs` (or `disabled/foo.rs`),
+//! which is compiled as `foo` when the feature is disabled.
+//! The stub can expose the same API returning "feature not enabled" errors,
+//! so that the API can be called without `#[cfg(feature = "foo")]` everywhere:
+//!
+//! ```ignore
+//! dirmod::feature!(pub); // gzip.rs, gzip.disabled.rs, zstd.rs, disabled/zstd.rs
+//! ```
+//!
+//! Other cfg keys, like `target_arch` or custom `--cfg key="value"` flags,
+//! are supported by `dirmod::cfg_key!()`, which takes the key as its first argument:
+//!
+//! ```ignore
+//! dirmod::cfg_key!(target_pointer_width; pub use ||); // 32.rs, 64.rs
+//! ```
+//!
+//! Modules selected by boolean cfg flags, like `loom.rs` and `std.rs`,
+//! are supported by `dirmod::cfg_flag!()`.
+//! With `||` followed by a module name, that module is compiled if none of the flags are enabled,
+//! and if multiple flags are enabled, only the first module in alphabetical (or `order`) order is compiled:
+//!
+//! ```ignore
+//! dirmod::cfg_flag!(pub use || std); // loom.rs under `#[cfg(loom)]`, std.rs otherwise
+//! ```
+//!
+//! Individual modules can be compiled conditionally with the `cfg` argument,
+//! which also applies to their re-exports:
+//!
+//! ```ignore
+//! dirmod::all!(default pub use; cfg(feature = "serde") serde_impl, json);
+//! ```
+//!
+//! Test-only modules, i.e. `tests`, `*_tests`, `test_util` and `testing` by default,
+//! are declared with `#[cfg(test)]` and never re-exported.
+//! The `tests` argument specifies the test-only modules instead:
+//!
+//! ```ignore
+//! dirmod::all!(default pub use; tests "*_spec", fixtures);
+//! ```
+//!
+//! Platform-specific variants can also be mixed with other modules in the same directory.
+//! `dirmod::all!()` declares files like `sys.unix.rs` and `sys.linux.rs` as `mod sys`
+//! under the matching `target_family` or `target_os`,
+//! and `sys.rs` as the fallback for other targets:
+//!
+//! ```ignore
+//! // sys.rs, sys.unix.rs, sys.linux.rs and sys.windows.rs become `mod sys`
+//! dirmod::all!(default pub);
+//! ```
 //!
 //! [File an issue][gh-issues] if I missed any common styles!
 //!
@@ -127,6 +279,43 @@
 //! dirmod::all!(except corge, grault);
 //! ```
 //!
+//! ## Other directories
+//! The `in` argument scans another directory inside the workspace,
+//! relative to the calling file or, with `crate`, to the crate root:
+//!
+//! ```ignore
+//! mod protocol {
+//!     dirmod::all!(in "../../shared/protocol"; default pub);
+//! }
+//! mod sys {
+//!     dirmod::os!(in crate "sys"; pub use ||);
+//! }
+//! ```
+//!
+//! ### Generated modules
+//! Modules generated by build scripts into a directory in `OUT_DIR`,
+//! e.g. by protobuf or bindgen, can be declared with `out_dir!`:
+//!
+//! ```ignore
+//! mod protos {
+//!     dirmod::out_dir!("protos", pub use);
+//! }
+//! ```
+//!
+//! Generated modules are declared with `#[allow(clippy::all)]` unless the `lints` argument is given.
+//!
+//! ## Build scripts
+//! If the macros are not suitable for your toolchain or tools,
+//! the [`dirmod-build`][dirmod-build] crate generates the same `mod` statements from build.rs
+//! into `OUT_DIR`, which can then be pulled in with `include!`.
+//!
+//! ```ignore
+//! // build.rs
+//! dirmod_build::Build::all("src/handlers", "default pub use").write("handlers.rs").unwrap();
+//! // src/handlers.rs
+//! include!(concat!(env!("OUT_DIR"), "/handlers.rs"));
+//! ```
+//!
 //! ## Documentation
 //! Instead of writing docs in mod.rs, write them in the module directly.
 //! In addition to `dirmod` constraints, there are a few advantages:
@@ -142,8 +331,9 @@
 //! ```
 //!
 //! ## Supported Rust versions
-//! Since detecting the source file requires the [`proc_macro_span`][proc-macro-span-issue] feature,
-//! Rust Nightly is required to compile this crate.
+//! Detecting the source file requires the `Span::local_file` API, which is stable since Rust 1.88.
+//! Older nightly toolchains are also supported through the
+//! [`proc_macro_span`][proc-macro-span-issue] feature.
 //!
 //! ## Examples
 //! See the [`testcrate`][testcrate-blob] directory, which demonstrates the use of `dirmod::all!` and `dirmod::family!`.
@@ -161,6 +351,14 @@
 //! The most straightforward alternative for now is to run `rustfmt src/**/*.rs`
 //! with `shopt -s globstar` enabled on a Linux shell.
 //!
+//! ### New files on stable toolchains
+//! On nightly toolchains, `dirmod` registers the scanned directories as dependencies of the crate,
+//! so adding or removing a module file recompiles the crate automatically.
+//! This mechanism (the `proc_macro_tracked_path` feature) is not stable yet,
+//! so on stable toolchains the new module is only declared after the calling file is modified
+//! (or after `cargo clean`).
+//! [`dirmod-build`][dirmod-build] does not have this issue.
+//!
 //! ### Error reporting
 //! The Rust compiler may fail to locate syntax error locations correctly
 //! ([known issue][compiler-issue]).
@@ -170,6 +368,7 @@
 //! [rust-2018]: https://doc.rust-lang.org/edition-guide/rust-2018/index.html
 //! [gh-issues]: https://github.com/SOF3/dirmod
 //! [proc-macro-span-issue]: https://github.com/rust-lang/rust/issues/54725
+//! [dirmod-build]: https://docs.rs/dirmod-build
 //! [testcrate-blob]: https://github.com/SOF3/dirmod/tree/master/testcrate
 //! [bnf-blob]: https://github.com/SOF3/dirmod/blob/master/syntax.bnf
 //! [rustfmt-issue]: https://github.com/rust-lang/rustfmt/issues/3253
@@ -0,0 +1,37 @@
+// dirmod
+// Copyright (C) SOFe
+//
+// Licensed under the Apache License, Version 2.0 (the License);
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an AS IS BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+fn main() {
+    println!("cargo:rustc-check-cfg=cfg(dirmod_nightly)");
+    if version_check::is_feature_flaggable().unwrap_or(false) {
+        println!("cargo:rustc-cfg=dirmod_nightly");
+    }
+
+    dirmod_build::Build::all("src/plugh", "default file pub use; default dir pub")
+        .write("plugh.rs")
+        .unwrap();
+    dirmod_build::Build::all("src/quux", "default pub")
+        .write("quux.rs")
+        .unwrap();
+
+    // mimics the output of code generators, which need not be clippy-clean
+    let generated = std::path::Path::new(&std::env::var_os("OUT_DIR").unwrap()).join("generated");
+    std::fs::create_dir_all(&generated).unwrap();
+    std::fs::write(
+        generated.join("answer.rs"),
+        "pub fn answer() -> u32 { return 42; }\n",
+    )
+    .unwrap();
+}
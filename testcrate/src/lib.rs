@@ -14,7 +14,23 @@
 // limitations under the License.
 
 #[cfg(test)]
-dirmod::all!(except corge);
+dirmod::all!(except corge, suite);
+
+mod suite;
+
+#[cfg(test)]
+mod generated {
+    dirmod::out_dir!("generated", pub use);
+}
+
+#[cfg(test)]
+mod shared {
+    dirmod::all!(in crate "shared"; default pub);
+
+    pub mod sys {
+        dirmod::family!(in "../shared/sys"; pub use ||);
+    }
+}
 
 #[cfg(test)]
 #[test]
@@ -33,4 +49,97 @@ pub fn test() {
     assert_eq!(dir::FILE, "file");
     assert_eq!(dir::subdir::SUBDIR, "subdir");
     assert_eq!(dir::subdir::SUBMOD, "submod");
+
+    assert_eq!(grault::GARPLY, "garply");
+    assert_eq!(grault::waldo::FRED, "fred");
+
+    assert_eq!(plugh::XYZZY, "xyzzy");
+    assert_eq!(plugh::thud::inner::INNER, "inner");
+    assert_eq!(quux::wobble::WOBBLE, "wobble");
+
+    assert_eq!(tree::ROOT, "root");
+    assert_eq!(tree::branch::TWIG, "twig");
+    assert_eq!(tree::branch::bud::LEAF, "leaf");
+
+    assert_eq!(names::r#type::TYPE, "type");
+    assert_eq!(names::my_module::MY_MODULE, "my-module");
+    assert_eq!(names::v001_init::INIT, "001_init");
+
+    assert_eq!(patterns::handler_login::LOGIN, "login");
+    assert_eq!(patterns::internal_util::UTIL, "util");
+
+    assert_eq!(clash::both::BOTH, "both/mod.rs");
+
+    assert_eq!(platform::common::COMMON, "common");
+    assert_eq!(platform::hidden::HIDDEN, "hidden");
+    #[cfg(unix)]
+    assert_eq!(platform::unix_only::UNIX_ONLY, "unix_only");
+    assert_eq!(
+        platform::sys::SYS,
+        if cfg!(target_os = "linux") {
+            "linux"
+        } else if cfg!(target_family = "unix") {
+            "unix"
+        } else if cfg!(target_family = "windows") {
+            "windows"
+        } else {
+            "generic"
+        }
+    );
+
+    assert_eq!(generated::answer(), 42);
+
+    assert_eq!(width::WIDTH, std::mem::size_of::<usize>() * 8);
+    assert_eq!(profile::PROFILE, "test");
+    assert_eq!(
+        format::FORMAT,
+        if cfg!(feature = "yaml") {
+            "yaml"
+        } else if cfg!(feature = "json-lines") {
+            "json-lines"
+        } else {
+            "json"
+        }
+    );
+    assert_eq!(
+        compress::gzip::compress(b"dirmod"),
+        if cfg!(feature = "gzip") {
+            Ok(b"dirmod".to_vec())
+        } else {
+            Err("feature `gzip` is not enabled")
+        }
+    );
+    assert_eq!(
+        compress::zstd::compress(b"dirmod"),
+        if cfg!(feature = "zstd") {
+            Ok(b"dirmod".to_vec())
+        } else {
+            Err("feature `zstd` is not enabled")
+        }
+    );
+    assert_eq!(codec::SELECTED, codec::imp::CODEC);
+    #[cfg(feature = "alpha")]
+    assert_eq!(codec::SELECTED, "alpha");
+    #[cfg(feature = "beta")]
+    assert_eq!(codec::beta::CODEC, "beta");
+    assert_eq!(kernel::common::COMMON, "common");
+    let kernel = if cfg!(target_os = "linux") {
+        "linux"
+    } else if cfg!(target_os = "windows") {
+        "windows"
+    } else {
+        "generic"
+    };
+    assert_eq!(kernel::KERNEL, kernel);
+    assert_eq!(kernel::selected(), (kernel, kernel));
+
+    assert_eq!(shared::protocol::PROTOCOL, "protocol");
+    assert_eq!(
+        shared::sys::SYS,
+        if cfg!(target_family = "unix") {
+            "unix"
+        } else {
+            "windows"
+        }
+    );
 }
@@ -0,0 +1,27 @@
+// dirmod
+// Copyright (C) SOFe
+//
+// Licensed under the Apache License, Version 2.0 (the License);
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an AS IS BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#[cfg(not(test))]
+compile_error!("suite_tests.rs is only compiled in tests");
+
+// not re-exported, otherwise this would be ambiguous with `tests::helper`
+pub fn helper() -> u32 {
+    super::testing::fixture() + 1
+}
+
+#[test]
+fn test_fixture() {
+    assert_eq!(helper(), 8);
+}
@@ -0,0 +1,75 @@
+// dirmod
+// Copyright (C) SOFe
+//
+// Licensed under the Apache License, Version 2.0 (the License);
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an AS IS BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Checks that adding a file to a scanned directory expands the macro again.
+//!
+//! Directory tracking requires a nightly toolchain.
+
+#![cfg(dirmod_nightly)]
+
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Output};
+use std::thread;
+use std::time::Duration;
+
+fn build(root: &Path) -> Output {
+    Command::new(env::var_os("CARGO").unwrap_or_else(|| "cargo".into()))
+        .args(["build", "--offline"])
+        .current_dir(root)
+        .env("CARGO_TARGET_DIR", root.join("target"))
+        .output()
+        .expect("failed to run cargo")
+}
+
+#[test]
+fn test_new_file_triggers_expansion() {
+    let root = Path::new(env!("CARGO_TARGET_TMPDIR")).join("tracking");
+    let _ = fs::remove_dir_all(&root);
+    fs::create_dir_all(root.join("src")).unwrap();
+
+    let dirmod = Path::new(env!("CARGO_MANIFEST_DIR")).parent().unwrap();
+    fs::write(
+        root.join("Cargo.toml"),
+        format!(
+            "[package]\nname = \"tracking\"\nversion = \"0.0.0\"\nedition = \"2018\"\n\n\
+             [dependencies]\ndirmod = {{path = {:?}}}\n\n[workspace]\n",
+            dirmod
+        ),
+    )
+    .unwrap();
+    fs::write(root.join("src/lib.rs"), "dirmod::all!(default pub);\n").unwrap();
+    fs::write(root.join("src/foo.rs"), "").unwrap();
+
+    let output = build(&root);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    // make sure the new file is observably newer than the previous build
+    thread::sleep(Duration::from_secs(1));
+    fs::write(
+        root.join("src/bar.rs"),
+        "compile_error!(\"bar.rs is declared\");\n",
+    )
+    .unwrap();
+
+    let output = build(&root);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("bar.rs is declared"));
+}